@@ -5,8 +5,18 @@ use serde::{
 };
 use std::fmt::{self, Display, Formatter};
 use std::ops::Deref;
+use std::str::FromStr;
 
-#[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
+// Upper bound of each CompactSize encoding width; the next width starts one above it
+pub const COMPACT_U8_MAX: u64 = 0xFC;
+pub const COMPACT_U16_MAX: u64 = 0xFFFF;
+pub const COMPACT_U32_MAX: u64 = 0xFFFF_FFFF;
+
+const COMPACT_U16_MIN: u64 = COMPACT_U8_MAX + 1;
+const COMPACT_U32_MIN: u64 = COMPACT_U16_MAX + 1;
+const COMPACT_U64_MIN: u64 = COMPACT_U32_MAX + 1;
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Serialize, Deserialize)]
 pub struct CompactSize {
     pub value: u64,
 }
@@ -17,6 +27,15 @@ pub enum BitcoinError {
     InvalidFormat,
 }
 
+// Fail with InsufficientBytes unless at least `needed` bytes are available
+fn ensure_len(bytes: &[u8], needed: usize) -> Result<(), BitcoinError> {
+    if bytes.len() < needed {
+        Err(BitcoinError::InsufficientBytes)
+    } else {
+        Ok(())
+    }
+}
+
 impl CompactSize {
     // Construct a CompactSize from a u64 value
     pub fn new(value: u64) -> Self {
@@ -25,6 +44,13 @@ impl CompactSize {
 
     // Encode according to Bitcoin's CompactSize format:
     pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_len());
+        self.write_to(&mut bytes);
+        bytes
+    }
+
+    // Append the encoding to an existing buffer
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
         // [0x00–0xFC] => 1 byte
         // [0xFDxxxx] => 0xFD + u16 (2 bytes)
         // [0xFExxxxxxxx] => 0xFE + u32 (4 bytes)
@@ -33,82 +59,259 @@ impl CompactSize {
         let val = self.value;
 
         match val {
-            // Concat slice containing header byte (0xF?) and slice repr of integer-bytes conversion
-            0..=252 => u8::to_le_bytes(val as u8).into(),
-            253..=65535 => [&[253u8], u16::to_le_bytes(val as u16).as_slice()].concat(),
-            65536..=4294967295 => [&[254u8], u32::to_le_bytes(val as u32).as_slice()].concat(),
-            4294967296..=u64::MAX => [&[255u8], val.to_le_bytes().as_slice()].concat(),
+            // Header byte (0xF?) followed by the little-endian integer bytes
+            0..=COMPACT_U8_MAX => buf.push(val as u8),
+            COMPACT_U16_MIN..=COMPACT_U16_MAX => {
+                buf.push(253);
+                buf.extend((val as u16).to_le_bytes());
+            }
+            COMPACT_U32_MIN..=COMPACT_U32_MAX => {
+                buf.push(254);
+                buf.extend((val as u32).to_le_bytes());
+            }
+            COMPACT_U64_MIN..=u64::MAX => {
+                buf.push(255);
+                buf.extend(val.to_le_bytes());
+            }
+        }
+    }
+
+    // Decode from any byte iterator, consuming only the prefix and the bytes it announces.
+    // Running out mid-read is InsufficientBytes. Not FromIterator: it reads one value and
+    // leaves the rest of the iterator untouched.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_iter<I: Iterator<Item = u8>>(iter: &mut I) -> Result<Self, BitcoinError> {
+        let prefix = iter.next().ok_or(BitcoinError::InsufficientBytes)?;
+        let width = match prefix {
+            0..=252 => return Ok(Self::new(prefix as u64)),
+            253 => 2,
+            254 => 4,
+            255 => 8,
+        };
+
+        let mut value_bytes = [0u8; 8];
+        for byte in value_bytes.iter_mut().take(width) {
+            *byte = iter.next().ok_or(BitcoinError::InsufficientBytes)?;
+        }
+        Ok(Self::new(u64::from_le_bytes(value_bytes)))
+    }
+
+    // Number of bytes the value occupies once encoded (1, 3, 5 or 9)
+    pub fn encoded_len(&self) -> usize {
+        match self.value {
+            0..=COMPACT_U8_MAX => 1,
+            COMPACT_U16_MIN..=COMPACT_U16_MAX => 3,
+            COMPACT_U32_MIN..=COMPACT_U32_MAX => 5,
+            COMPACT_U64_MIN..=u64::MAX => 9,
         }
     }
 
+    // The value as a length or count; InvalidFormat if it doesn't fit in usize (32-bit targets)
+    pub fn as_usize(&self) -> Result<usize, BitcoinError> {
+        usize::try_from(self.value).map_err(|_| BitcoinError::InvalidFormat)
+    }
+
+    // Check whether two values use the same encoding width
+    pub fn same_encoded_width(&self, other: &CompactSize) -> bool {
+        self.encoded_len() == other.encoded_len()
+    }
+
     // Decode CompactSize, returning value and number of bytes consumed.
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
         // Check if bytes is empty.
-        let len = bytes.len();
+        ensure_len(bytes, 1)?;
 
-        if len == 0 {
-            Err(BitcoinError::InsufficientBytes)
-        } else {
-            // Check that enough bytes are available based on prefix.
-            let prefix = bytes[0];
+        // Check that enough bytes are available based on prefix.
+        let prefix = bytes[0];
 
-            match prefix {
-                0..=252 => {
-                    let value = u8::from_le_bytes([prefix]);
-                    Ok((Self::new(value as u64), 1))
-                }
-                253 => {
-                    if len < 3 {
-                        Err(BitcoinError::InsufficientBytes)
-                    } else {
-                        let mut bytes_array = [0; 2];
-                        bytes_array.copy_from_slice(&bytes[1..3]);
-
-                        let value = u16::from_le_bytes(bytes_array);
-                        Ok((Self::new(value as u64), 3))
-                    }
-                }
-                254 => {
-                    if len < 5 {
-                        Err(BitcoinError::InsufficientBytes)
-                    } else {
-                        let mut bytes_array = [0; 4];
-                        bytes_array.copy_from_slice(&bytes[1..5]);
-
-                        let value = u32::from_le_bytes(bytes_array);
-                        Ok((Self::new(value as u64), 5))
-                    }
-                }
-                255 => {
-                    if len < 9 {
-                        Err(BitcoinError::InsufficientBytes)
-                    } else {
-                        let mut bytes_array = [0; 8];
-                        bytes_array.copy_from_slice(&bytes[1..9]);
-
-                        let value = u64::from_le_bytes(bytes_array);
-                        Ok((Self::new(value), 9))
-                    }
-                }
+        match prefix {
+            0..=252 => {
+                let value = u8::from_le_bytes([prefix]);
+                Ok((Self::new(value as u64), 1))
+            }
+            253 => {
+                ensure_len(bytes, 3)?;
+                let mut bytes_array = [0; 2];
+                bytes_array.copy_from_slice(&bytes[1..3]);
+
+                let value = u16::from_le_bytes(bytes_array);
+                Ok((Self::new(value as u64), 3))
+            }
+            254 => {
+                ensure_len(bytes, 5)?;
+                let mut bytes_array = [0; 4];
+                bytes_array.copy_from_slice(&bytes[1..5]);
+
+                let value = u32::from_le_bytes(bytes_array);
+                Ok((Self::new(value as u64), 5))
+            }
+            255 => {
+                ensure_len(bytes, 9)?;
+                let mut bytes_array = [0; 8];
+                bytes_array.copy_from_slice(&bytes[1..9]);
+
+                let value = u64::from_le_bytes(bytes_array);
+                Ok((Self::new(value), 9))
             }
         }
     }
 }
 
+impl Display for CompactSize {
+    // Format as "value (N-byte)", e.g. "300 (3-byte)"
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{} ({}-byte)", self.value, self.encoded_len())
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub struct Txid(pub [u8; 32]);
 
+impl Txid {
+    // Build a Txid from a (possibly short) prefix, copying up to 32 bytes and zero-padding
+    // the rest. Only meant for display/matching: the result is not a real transaction hash.
+    pub fn from_prefix(prefix: &[u8]) -> Txid {
+        let mut bytes = [0u8; 32];
+        let len = prefix.len().min(32);
+        bytes[..len].copy_from_slice(&prefix[..len]);
+        Txid(bytes)
+    }
+
+    // Check whether the txid bytes start with the given prefix (longer than 32 never matches)
+    pub fn matches_prefix(&self, prefix: &[u8]) -> bool {
+        self.0.starts_with(prefix)
+    }
+
+    // Hex in display order (byte-reversed), as shown by explorers and RPC
+    fn display_hex(&self) -> String {
+        let mut display_bytes = self.0;
+        display_bytes.reverse();
+        encode(display_bytes)
+    }
+
+    // Lenient counterpart to from_str for explorer-style search: 1-64 display-order hex
+    // chars (optional 0x prefix), zero-padded on the right. Anything else is InvalidFormat.
+    pub fn from_str_prefix(s: &str) -> Result<TxidPrefix, BitcoinError> {
+        let hex_str = strip_hex_prefix(s);
+        if hex_str.is_empty()
+            || hex_str.len() > 64
+            || !hex_str.bytes().all(|c| c.is_ascii_hexdigit())
+        {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        let padded = format!("{:0<64}", hex_str);
+        let display_bytes = decode(padded).map_err(|_| BitcoinError::InvalidFormat)?;
+        Ok(TxidPrefix {
+            display_bytes: display_bytes.try_into().unwrap(),
+            nibbles: hex_str.len(),
+        })
+    }
+}
+
+// Tolerate an optional 0x/0X prefix used by some JSON sources
+fn strip_hex_prefix(s: &str) -> &str {
+    s.strip_prefix("0x")
+        .or_else(|| s.strip_prefix("0X"))
+        .unwrap_or(s)
+}
+
+impl FromStr for Txid {
+    type Err = BitcoinError;
+
+    // Parse exactly 64 hex chars in display order (as printed by explorers and RPC),
+    // with an optional 0x prefix. Serde's hex form keeps internal byte order instead.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let hex_str = strip_hex_prefix(s);
+        if hex_str.len() != 64 {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        let mut bytes: [u8; 32] = decode(hex_str)
+            .map_err(|_| BitcoinError::InvalidFormat)?
+            .try_into()
+            .unwrap();
+        bytes.reverse();
+        Ok(Txid(bytes))
+    }
+}
+
+// Display-order txid prefix from Txid::from_str_prefix, matched nibble by nibble
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TxidPrefix {
+    display_bytes: [u8; 32],
+    nibbles: usize,
+}
+
+impl TxidPrefix {
+    // Whether the txid's display hex starts with this prefix
+    pub fn matches(&self, txid: &Txid) -> bool {
+        let mut display_bytes = txid.0;
+        display_bytes.reverse();
+
+        let full_bytes = self.nibbles / 2;
+        if display_bytes[..full_bytes] != self.display_bytes[..full_bytes] {
+            return false;
+        }
+
+        // An odd-length prefix also constrains the high nibble of the next byte
+        self.nibbles.is_multiple_of(2)
+            || display_bytes[full_bytes] >> 4 == self.display_bytes[full_bytes] >> 4
+    }
+}
+
+impl From<[u8; 32]> for Txid {
+    fn from(bytes: [u8; 32]) -> Self {
+        Txid(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for Txid {
+    type Error = BitcoinError;
+
+    // Fails with InvalidFormat unless the slice is exactly 32 bytes
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        let bytes_array: [u8; 32] = bytes.try_into().map_err(|_| BitcoinError::InvalidFormat)?;
+        Ok(Txid(bytes_array))
+    }
+}
+
 impl Serialize for Txid {
-    // Serialize Txid byte field as a hex-encoded string (32 bytes => 64 hex chars)
+    // Serialize Txid byte field as a hex-encoded string (32 bytes => 64 hex chars) for
+    // human-readable formats, and as the raw 32 bytes for binary formats
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
+        if !serializer.is_human_readable() {
+            return serializer.serialize_bytes(&self.0);
+        }
+
         let hex_str = encode(self.0);
         serializer.serialize_str(&hex_str)
     }
 }
 
+struct BytesVisitor;
+
+impl<'de> Visitor<'de> for BytesVisitor {
+    // Accept exactly 32 raw bytes for binary formats
+    type Value = [u8; 32];
+
+    fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("32 raw bytes")
+    }
+
+    fn visit_bytes<E>(self, value: &[u8]) -> Result<Self::Value, E>
+    where
+        E: Error,
+    {
+        value
+            .try_into()
+            .map_err(|_| Error::invalid_length(value.len(), &self))
+    }
+}
+
 struct StringVisitor;
 
 impl<'de> Visitor<'de> for StringVisitor {
@@ -140,11 +343,20 @@ impl<'de> Deserialize<'de> for Txid {
     where
         D: Deserializer<'de>,
     {
+        // Binary formats carry the raw bytes directly
+        if !deserializer.is_human_readable() {
+            let bytes_array = deserializer.deserialize_bytes(BytesVisitor)?;
+            return Ok(Txid(bytes_array));
+        }
+
         // Call deserializer string method to obtain string from visitor
-        let hex_str = deserializer.deserialize_string(StringVisitor).unwrap();
+        let hex_str = deserializer.deserialize_string(StringVisitor)?;
+
+        // Tolerate an optional 0x/0X prefix used by some JSON sources
+        let hex_str = strip_hex_prefix(&hex_str);
 
         // Parse hex string into 32-byte array
-        let raw_bytes = decode(hex_str).unwrap();
+        let raw_bytes = decode(hex_str).map_err(D::Error::custom)?;
 
         // Validate length of hex bytes after decoding
         if raw_bytes.len() != 32 {
@@ -172,29 +384,60 @@ impl OutPoint {
         }
     }
 
+    // Create an OutPoint from a txid in display order (as shown by explorers and RPC),
+    // parsed with Txid::from_str so both accept exactly the same strings
+    pub fn from_display_txid(txid_hex: &str, vout: u32) -> Result<OutPoint, BitcoinError> {
+        Ok(OutPoint {
+            txid: txid_hex.parse()?,
+            vout,
+        })
+    }
+
+    // The null outpoint (all-zero txid, vout 0xFFFFFFFF) spent by coinbase inputs
+    pub fn is_null(&self) -> bool {
+        self.txid.0 == [0u8; 32] && self.vout == u32::MAX
+    }
+
     // Serialize as: txid (32 bytes) + vout (4 bytes, little-endian)
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes_vec = vec![0; 32];
-        bytes_vec.copy_from_slice(&self.txid.0);
-        bytes_vec.extend_from_slice(&self.vout.to_le_bytes());
+        let mut bytes_vec = Vec::with_capacity(36);
+        self.write_to(&mut bytes_vec);
         bytes_vec
     }
 
+    // Append the 36-byte serialization to an existing buffer
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(&self.txid.0);
+        buf.extend_from_slice(&self.vout.to_le_bytes());
+    }
+
     // Deserialize 36 bytes: txid[0..32], vout[32..36]
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
         // Return error if insufficient bytes
-        if bytes.len() < 36 {
-            Err(BitcoinError::InsufficientBytes)
-        } else {
-            // Create txid byte array from bytes slice and craft Txid struct instance
-            let txid_array: [u8; 32] = bytes[0..32].try_into().unwrap();
-            let txid = Txid(txid_array);
+        ensure_len(bytes, 36)?;
+
+        // Create txid byte array from bytes slice and craft Txid struct instance
+        let txid_array: [u8; 32] = bytes[0..32].try_into().unwrap();
+        let txid = Txid(txid_array);
 
-            // Create vout byte array from bytes slice and obtain vout integer
-            let vout_array: [u8; 4] = bytes[32..36].try_into().unwrap();
-            let vout = u32::from_le_bytes(vout_array);
+        // Create vout byte array from bytes slice and obtain vout integer
+        let vout_array: [u8; 4] = bytes[32..36].try_into().unwrap();
+        let vout = u32::from_le_bytes(vout_array);
 
-            Ok((OutPoint { txid, vout }, 36))
+        Ok((OutPoint { txid, vout }, 36))
+    }
+}
+
+impl Display for OutPoint {
+    // Format as "<display txid>:<vout>"; the coinbase outpoint renders as "coinbase" and a
+    // 0xFFFFFFFF vout on any other txid is shown in hex rather than as 4294967295
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        if self.is_null() {
+            write!(f, "coinbase")
+        } else if self.vout == u32::MAX {
+            write!(f, "{}:0xffffffff", self.txid.display_hex())
+        } else {
+            write!(f, "{}:{}", self.txid.display_hex(), self.vout)
         }
     }
 }
@@ -210,42 +453,511 @@ impl Script {
         Self { bytes }
     }
 
+    // BIP-143 scriptCode for a P2SH-P2WPKH (nested segwit) input:
+    // OP_DUP OP_HASH160 <pubkey_hash> OP_EQUALVERIFY OP_CHECKSIG. This, not the redeem
+    // script pushed in the scriptSig, is what gets committed to in the segwit sighash.
+    pub fn nested_p2wpkh_script_code(pubkey_hash: [u8; 20]) -> Script {
+        let mut bytes = Vec::with_capacity(25);
+        bytes.extend([0x76, 0xa9, 0x14]);
+        bytes.extend(pubkey_hash);
+        bytes.extend([0x88, 0xac]);
+        Script::new(bytes)
+    }
+
+    // Taproot key-path scriptPubKey: OP_1 <32-byte x-only output key>
+    pub fn new_p2tr(output_key: [u8; 32]) -> Script {
+        let mut bytes = Vec::with_capacity(34);
+        bytes.extend([0x51, 0x20]);
+        bytes.extend(output_key);
+        Script::new(bytes)
+    }
+
+    // Whether this is a taproot output: a v1 witness program of 32 bytes
+    pub fn is_p2tr(&self) -> bool {
+        matches!(self.witness_program(), Some((1, program)) if program.len() == 32)
+    }
+
+    // Borrow the raw script bytes without relying on Deref coercion
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    // Length of the raw script (excluding the CompactSize prefix)
+    pub fn len(&self) -> usize {
+        self.bytes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bytes.is_empty()
+    }
+
     // Prefix with CompactSize (length), then raw bytes
     pub fn to_bytes(&self) -> Vec<u8> {
         let len = self.bytes.len();
 
         // Store size of bytes slice as prefix: CompactSize { bytes_len }
         let size = CompactSize::new(len as u64);
-        let mut prefix = size.to_bytes();
 
-        // Append prefix and bytes to new vector
-        let mut bytes_vec = Vec::with_capacity(len);
-        bytes_vec.append(&mut prefix);
-        bytes_vec.extend(&self.bytes);
+        // Write prefix and bytes to new vector, reserving room for both up front
+        let mut bytes_vec = Vec::with_capacity(size.encoded_len() + len);
+        self.write_to(&mut bytes_vec);
 
         bytes_vec
     }
 
+    // Append the CompactSize length prefix and script bytes to an existing buffer
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        CompactSize::new(self.bytes.len() as u64).write_to(buf);
+        buf.extend_from_slice(&self.bytes);
+    }
+
     // Parse CompactSize prefix, then read that many bytes
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
-        if bytes.is_empty() {
-            return Err(BitcoinError::InsufficientBytes);
-        }
+        Self::from_bytes_with_options(bytes, &ParseOptions::permissive())
+    }
+
+    // Same as from_bytes, but also require the script's pushes to be well-formed
+    pub fn from_bytes_validated(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
+        let (script, consumed) = Self::from_bytes(bytes)?;
+        script.parse()?;
+        Ok((script, consumed))
+    }
+
+    fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), BitcoinError> {
+        ensure_len(bytes, 1)?;
 
         // Parse CompactSize prefix to get script length
-        let (compact_size, size_consumed) = CompactSize::from_bytes(bytes)?;
-        let script_len = compact_size.value as usize;
+        let (compact_size, size_consumed) = options.read_compact_size(bytes)?;
+        let script_len = compact_size.as_usize()?;
 
-        if bytes.len() < size_consumed + script_len {
-            return Err(BitcoinError::InsufficientBytes);
+        if script_len > options.max_script_len {
+            return Err(BitcoinError::InvalidFormat);
         }
 
+        // A length near usize::MAX can't be present; don't let the sum wrap
+        let total_len = size_consumed
+            .checked_add(script_len)
+            .ok_or(BitcoinError::InsufficientBytes)?;
+        ensure_len(bytes, total_len)?;
+
         // Extract script bytes
-        let script_bytes = &bytes[size_consumed..size_consumed + script_len];
+        let script_bytes = &bytes[size_consumed..total_len];
         let script = Script::new(Vec::from(script_bytes));
 
-        Ok((script, size_consumed + script_len))
+        Ok((script, total_len))
+    }
+
+    // True when every operation is a data push (opcode <= OP_16), as required for
+    // standard scriptSigs. Malformed scripts are never push-only.
+    pub fn is_push_only(&self) -> bool {
+        match self.parse() {
+            Ok(instructions) => instructions.iter().all(|ins| ins.opcode <= OP_16),
+            Err(_) => false,
+        }
+    }
+
+    // BIP-62 rule 3: every data push uses the smallest encoding (OP_0, OP_1NEGATE and
+    // OP_1..OP_16 for their values, a direct push up to 75 bytes, then PUSHDATA1/2/4).
+    // Malformed scripts fail.
+    pub fn has_minimal_pushes(&self) -> bool {
+        let Ok(instructions) = self.parse() else {
+            return false;
+        };
+
+        instructions.iter().all(|ins| {
+            let Some(data) = ins.data else {
+                return true;
+            };
+
+            match data.len() {
+                0 => ins.opcode == 0x00,
+                1 if (1..=16).contains(&data[0]) || data[0] == 0x81 => false,
+                len @ 1..=75 => ins.opcode as usize == len,
+                76..=255 => ins.opcode == OP_PUSHDATA1,
+                256..=65535 => ins.opcode == OP_PUSHDATA2,
+                _ => ins.opcode == OP_PUSHDATA4,
+            }
+        })
+    }
+
+    // Every data push in order, including OP_0's empty push; OP_1..OP_16 are opcodes, not
+    // data. A malformed script yields no pushes.
+    pub fn pushed_data(&self) -> Vec<&[u8]> {
+        match self.parse() {
+            Ok(instructions) => instructions.iter().filter_map(|ins| ins.data).collect(),
+            Err(_) => vec![],
+        }
+    }
+
+    // Disassemble into space-separated opcodes, with pushed data as hex.
+    // Returns "[error]" for a script whose pushes run past the end.
+    pub fn to_asm(&self) -> String {
+        let Ok(instructions) = self.parse() else {
+            return String::from("[error]");
+        };
+
+        instructions
+            .iter()
+            .map(|ins| match ins.data {
+                Some(data) if !data.is_empty() => encode(data),
+                _ => opcode_name(ins.opcode).to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Disassembly followed by a note naming the recognized script pattern, if any
+    pub fn to_asm_annotated(&self) -> String {
+        let asm = self.to_asm();
+
+        match self.pattern_note() {
+            Some(note) => format!("{} // {}", asm, note),
+            None => asm,
+        }
+    }
+
+    // Address for a standard output script (P2PKH, P2SH, segwit v0, P2TR) on the given
+    // network. Non-standard scripts have no address.
+    pub fn to_address(&self, network: Network) -> Option<String> {
+        let b = &self.bytes;
+
+        if b.len() == 25
+            && b[0] == 0x76
+            && b[1] == 0xa9
+            && b[2] == 0x14
+            && b[23] == 0x88
+            && b[24] == 0xac
+        {
+            Some(base58check_encode(network.p2pkh_version(), &b[3..23]))
+        } else if b.len() == 23 && b[0] == 0xa9 && b[1] == 0x14 && b[22] == 0x87 {
+            Some(base58check_encode(network.p2sh_version(), &b[2..22]))
+        } else if let Some((version, program)) = self.witness_program() {
+            // v0 programs are only P2WPKH (20 bytes) or P2WSH (32 bytes)
+            if version == 0 && program.len() != 20 && program.len() != 32 {
+                None
+            } else {
+                Some(segwit_encode(network.bech32_hrp(), version, &program))
+            }
+        } else {
+            None
+        }
+    }
+
+    // Witness version and program of a segwit scriptPubKey: OP_0..OP_16 followed by a
+    // single direct push of 2-40 bytes
+    pub fn witness_program(&self) -> Option<(u8, Vec<u8>)> {
+        let b = &self.bytes;
+        if b.len() < 4 || b.len() > 42 || b[1] as usize != b.len() - 2 {
+            return None;
+        }
+
+        let version = match b[0] {
+            0x00 => 0,
+            0x51..=OP_16 => b[0] - 0x50,
+            _ => return None,
+        };
+        Some((version, b[2..].to_vec()))
+    }
+
+    // Signature operations as counted by Bitcoin Core's GetSigOpCount: CHECKSIG(VERIFY)
+    // counts 1, CHECKMULTISIG(VERIFY) counts 20, or with `accurate` the preceding OP_1..OP_16
+    // key count. Counting stops at a malformed push.
+    pub fn sigop_count(&self, accurate: bool) -> usize {
+        let (instructions, _) = self.parse_prefix();
+        let mut count = 0;
+        let mut last_opcode = None;
+
+        for ins in &instructions {
+            match ins.opcode {
+                OP_CHECKSIG | OP_CHECKSIGVERIFY => count += 1,
+                OP_CHECKMULTISIG | OP_CHECKMULTISIGVERIFY => {
+                    count += match last_opcode {
+                        Some(n @ 0x51..=OP_16) if accurate => (n - 0x50) as usize,
+                        _ => MAX_PUBKEYS_PER_MULTISIG,
+                    }
+                }
+                _ => {}
+            }
+            last_opcode = Some(ins.opcode);
+        }
+
+        count
+    }
+
+    // Name of the standard template this script matches
+    fn pattern_note(&self) -> Option<&'static str> {
+        let b = &self.bytes;
+
+        if b.len() == 25
+            && b[0] == 0x76
+            && b[1] == 0xa9
+            && b[2] == 0x14
+            && b[23] == 0x88
+            && b[24] == 0xac
+        {
+            Some("P2PKH spend")
+        } else if b.len() == 23 && b[0] == 0xa9 && b[1] == 0x14 && b[22] == 0x87 {
+            Some("P2SH")
+        } else if b.len() == 22 && b[0] == 0x00 && b[1] == 0x14 {
+            Some("P2WPKH")
+        } else if b.len() == 34 && b[0] == 0x00 && b[1] == 0x20 {
+            Some("P2WSH")
+        } else if b.len() == 34 && b[0] == 0x51 && b[1] == 0x20 {
+            Some("P2TR")
+        } else if b.first() == Some(&0x6a) {
+            Some("OP_RETURN data carrier")
+        } else {
+            None
+        }
+    }
+
+    // Split the script into instructions, failing if a push runs past the end
+    fn parse(&self) -> Result<Vec<Instruction<'_>>, BitcoinError> {
+        let (instructions, result) = self.parse_prefix();
+        result.map(|_| instructions)
+    }
+
+    // Instructions up to the first malformed push, plus whether the whole script parsed
+    fn parse_prefix(&self) -> (Vec<Instruction<'_>>, Result<(), BitcoinError>) {
+        let bytes = &self.bytes;
+        let mut instructions = Vec::new();
+        let mut offset = 0;
+
+        while offset < bytes.len() {
+            let opcode = bytes[offset];
+            offset += 1;
+
+            // Width of the explicit length field for OP_PUSHDATA1/2/4
+            let len_width = match opcode {
+                OP_PUSHDATA1 => 1,
+                OP_PUSHDATA2 => 2,
+                OP_PUSHDATA4 => 4,
+                _ => 0,
+            };
+
+            let push_len = if opcode < OP_PUSHDATA1 {
+                Some(opcode as usize)
+            } else if len_width > 0 {
+                if bytes.len() < offset + len_width {
+                    return (instructions, Err(BitcoinError::InvalidFormat));
+                }
+                let mut len_bytes = [0u8; 4];
+                len_bytes[..len_width].copy_from_slice(&bytes[offset..offset + len_width]);
+                offset += len_width;
+                Some(u32::from_le_bytes(len_bytes) as usize)
+            } else {
+                None
+            };
+
+            let data = match push_len {
+                Some(len) => {
+                    if bytes.len() - offset < len {
+                        return (instructions, Err(BitcoinError::InvalidFormat));
+                    }
+                    let data = &bytes[offset..offset + len];
+                    offset += len;
+                    Some(data)
+                }
+                None => None,
+            };
+
+            instructions.push(Instruction { opcode, data });
+        }
+
+        (instructions, Ok(()))
+    }
+}
+
+const OP_PUSHDATA1: u8 = 0x4c;
+const OP_PUSHDATA2: u8 = 0x4d;
+const OP_PUSHDATA4: u8 = 0x4e;
+const OP_16: u8 = 0x60;
+const OP_CHECKSIG: u8 = 0xac;
+const OP_CHECKSIGVERIFY: u8 = 0xad;
+const OP_CHECKMULTISIG: u8 = 0xae;
+const OP_CHECKMULTISIGVERIFY: u8 = 0xaf;
+
+// Sigops charged for a bare CHECKMULTISIG when the key count isn't known
+const MAX_PUBKEYS_PER_MULTISIG: usize = 20;
+
+// Bitcoin Core's name for a non-push opcode (pushes are printed as hex)
+fn opcode_name(opcode: u8) -> &'static str {
+    match opcode {
+        0x00 => "OP_0",
+        0x4c => "OP_PUSHDATA1",
+        0x4d => "OP_PUSHDATA2",
+        0x4e => "OP_PUSHDATA4",
+        0x4f => "OP_1NEGATE",
+        0x50 => "OP_RESERVED",
+        0x51 => "OP_1",
+        0x52 => "OP_2",
+        0x53 => "OP_3",
+        0x54 => "OP_4",
+        0x55 => "OP_5",
+        0x56 => "OP_6",
+        0x57 => "OP_7",
+        0x58 => "OP_8",
+        0x59 => "OP_9",
+        0x5a => "OP_10",
+        0x5b => "OP_11",
+        0x5c => "OP_12",
+        0x5d => "OP_13",
+        0x5e => "OP_14",
+        0x5f => "OP_15",
+        0x60 => "OP_16",
+        0x61 => "OP_NOP",
+        0x62 => "OP_VER",
+        0x63 => "OP_IF",
+        0x64 => "OP_NOTIF",
+        0x65 => "OP_VERIF",
+        0x66 => "OP_VERNOTIF",
+        0x67 => "OP_ELSE",
+        0x68 => "OP_ENDIF",
+        0x69 => "OP_VERIFY",
+        0x6a => "OP_RETURN",
+        0x6b => "OP_TOALTSTACK",
+        0x6c => "OP_FROMALTSTACK",
+        0x6d => "OP_2DROP",
+        0x6e => "OP_2DUP",
+        0x6f => "OP_3DUP",
+        0x70 => "OP_2OVER",
+        0x71 => "OP_2ROT",
+        0x72 => "OP_2SWAP",
+        0x73 => "OP_IFDUP",
+        0x74 => "OP_DEPTH",
+        0x75 => "OP_DROP",
+        0x76 => "OP_DUP",
+        0x77 => "OP_NIP",
+        0x78 => "OP_OVER",
+        0x79 => "OP_PICK",
+        0x7a => "OP_ROLL",
+        0x7b => "OP_ROT",
+        0x7c => "OP_SWAP",
+        0x7d => "OP_TUCK",
+        0x7e => "OP_CAT",
+        0x7f => "OP_SUBSTR",
+        0x80 => "OP_LEFT",
+        0x81 => "OP_RIGHT",
+        0x82 => "OP_SIZE",
+        0x83 => "OP_INVERT",
+        0x84 => "OP_AND",
+        0x85 => "OP_OR",
+        0x86 => "OP_XOR",
+        0x87 => "OP_EQUAL",
+        0x88 => "OP_EQUALVERIFY",
+        0x89 => "OP_RESERVED1",
+        0x8a => "OP_RESERVED2",
+        0x8b => "OP_1ADD",
+        0x8c => "OP_1SUB",
+        0x8d => "OP_2MUL",
+        0x8e => "OP_2DIV",
+        0x8f => "OP_NEGATE",
+        0x90 => "OP_ABS",
+        0x91 => "OP_NOT",
+        0x92 => "OP_0NOTEQUAL",
+        0x93 => "OP_ADD",
+        0x94 => "OP_SUB",
+        0x95 => "OP_MUL",
+        0x96 => "OP_DIV",
+        0x97 => "OP_MOD",
+        0x98 => "OP_LSHIFT",
+        0x99 => "OP_RSHIFT",
+        0x9a => "OP_BOOLAND",
+        0x9b => "OP_BOOLOR",
+        0x9c => "OP_NUMEQUAL",
+        0x9d => "OP_NUMEQUALVERIFY",
+        0x9e => "OP_NUMNOTEQUAL",
+        0x9f => "OP_LESSTHAN",
+        0xa0 => "OP_GREATERTHAN",
+        0xa1 => "OP_LESSTHANOREQUAL",
+        0xa2 => "OP_GREATERTHANOREQUAL",
+        0xa3 => "OP_MIN",
+        0xa4 => "OP_MAX",
+        0xa5 => "OP_WITHIN",
+        0xa6 => "OP_RIPEMD160",
+        0xa7 => "OP_SHA1",
+        0xa8 => "OP_SHA256",
+        0xa9 => "OP_HASH160",
+        0xaa => "OP_HASH256",
+        0xab => "OP_CODESEPARATOR",
+        0xac => "OP_CHECKSIG",
+        0xad => "OP_CHECKSIGVERIFY",
+        0xae => "OP_CHECKMULTISIG",
+        0xaf => "OP_CHECKMULTISIGVERIFY",
+        0xb0 => "OP_NOP1",
+        0xb1 => "OP_CHECKLOCKTIMEVERIFY",
+        0xb2 => "OP_CHECKSEQUENCEVERIFY",
+        0xb3 => "OP_NOP4",
+        0xb4 => "OP_NOP5",
+        0xb5 => "OP_NOP6",
+        0xb6 => "OP_NOP7",
+        0xb7 => "OP_NOP8",
+        0xb8 => "OP_NOP9",
+        0xb9 => "OP_NOP10",
+        0xba => "OP_CHECKSIGADD",
+        _ => "OP_UNKNOWN",
+    }
+}
+
+// A single script operation: the opcode and, for pushes, the pushed bytes
+struct Instruction<'a> {
+    opcode: u8,
+    data: Option<&'a [u8]>,
+}
+
+// Decode a script number: minimal little-endian magnitude with the sign in the top bit
+// of the last byte. The empty vector is 0. Numbers wider than 8 bytes are rejected.
+pub fn decode_script_num(bytes: &[u8]) -> Result<i64, BitcoinError> {
+    if bytes.len() > 8 {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let Some((&last, _)) = bytes.split_last() else {
+        return Ok(0);
+    };
+
+    let mut magnitude: u64 = 0;
+    for (i, &byte) in bytes.iter().enumerate() {
+        magnitude |= (byte as u64) << (8 * i);
+    }
+
+    // Clear the sign bit from the magnitude
+    let sign_bit = 0x80u64 << (8 * (bytes.len() - 1));
+    magnitude &= !sign_bit;
+
+    if last & 0x80 != 0 {
+        Ok(-(magnitude as i64))
+    } else {
+        Ok(magnitude as i64)
+    }
+}
+
+// Encode a script number using the minimal CScriptNum encoding. Only -i64::MAX..=i64::MAX
+// fits the 8 bytes decode_script_num accepts; i64::MIN needs a 9th byte for its sign and
+// so does not round-trip.
+pub fn encode_script_num(n: i64) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    let negative = n < 0;
+    let mut magnitude = n.unsigned_abs();
+
+    while magnitude > 0 {
+        bytes.push((magnitude & 0xff) as u8);
+        magnitude >>= 8;
+    }
+
+    // If the top bit is already used by the magnitude, add a byte to carry the sign
+    if let Some(last) = bytes.last_mut() {
+        if *last & 0x80 != 0 {
+            bytes.push(if negative { 0x80 } else { 0x00 });
+        } else if negative {
+            *last |= 0x80;
+        }
     }
+
+    bytes
 }
 
 impl Deref for Script {
@@ -257,6 +969,17 @@ impl Deref for Script {
     }
 }
 
+// BIP-68 view of an input's sequence number
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct SequenceBits {
+    // Bit 31: relative locktime disabled
+    pub disable_flag: bool,
+    // Bit 22: value is in 512-second units rather than blocks
+    pub type_flag: bool,
+    // Bits 0-15: relative locktime value
+    pub value_field: u16,
+}
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct TransactionInput {
     pub previous_output: OutPoint,
@@ -274,61 +997,210 @@ impl TransactionInput {
         }
     }
 
+    // Split the sequence number into its BIP-68 fields
+    pub fn sequence_bits(&self) -> SequenceBits {
+        SequenceBits {
+            disable_flag: self.sequence & (1 << 31) != 0,
+            type_flag: self.sequence & (1 << 22) != 0,
+            value_field: (self.sequence & 0xFFFF) as u16,
+        }
+    }
+
+    // Serialized scriptSig size: CompactSize length prefix plus the script bytes
+    pub fn script_sig_size(&self) -> usize {
+        let len = self.script_sig.bytes.len();
+        CompactSize::new(len as u64).encoded_len() + len
+    }
+
     // Serialize: OutPoint + Script (with CompactSize) + sequence (4 bytes LE)
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut tx_input_bytes = Vec::with_capacity(44);
-        tx_input_bytes.extend(&self.previous_output.to_bytes());
-        tx_input_bytes.extend(&self.script_sig.to_bytes());
-        tx_input_bytes.extend(&self.sequence.to_le_bytes());
+        self.write_to(&mut tx_input_bytes);
 
         tx_input_bytes
     }
 
+    // Append the serialization to an existing buffer
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        self.previous_output.write_to(buf);
+        self.script_sig.write_to(buf);
+        buf.extend_from_slice(&self.sequence.to_le_bytes());
+    }
+
     // Deserialize in order:
     // - OutPoint (36 bytes)
     // - Script (with CompactSize)
     // - Sequence (4 bytes)
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
-        let bytes_len = bytes.len();
+        Self::from_bytes_with_options(bytes, &ParseOptions::permissive())
+    }
 
-        if bytes_len < 36 {
-            Err(BitcoinError::InsufficientBytes)
-        } else {
-            // Construct outpoint using its from_bytes method
-            let (outpoint, outpoint_consumed) = OutPoint::from_bytes(&bytes[0..]).unwrap();
-            let mut offset = outpoint_consumed;
-
-            if outpoint_consumed != 36 {
-                Err(BitcoinError::InvalidFormat)
-            } else if bytes_len < offset {
-                Err(BitcoinError::InsufficientBytes)
-            } else {
-                // Construct script signature using its from_byte method, starting from outpoint offset
-                let (script_sig, script_consumed) = Script::from_bytes(&bytes[offset..]).unwrap();
-                offset += script_consumed;
-
-                if bytes_len < offset + 4 {
-                    Err(BitcoinError::InsufficientBytes)
-                } else {
-                    // Read sequence from leftover bytes and calculate total_consumed_bytes
-                    let sequence =
-                        u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
-                    let total_bytes_consumed = offset + 4;
-
-                    // Create tx_input struct and return
-                    let tx_input = TransactionInput {
-                        previous_output: outpoint,
-                        script_sig,
-                        sequence,
-                    };
-
-                    Ok((tx_input, total_bytes_consumed))
-                }
-            }
+    fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), BitcoinError> {
+        // Construct outpoint using its from_bytes method
+        let (outpoint, outpoint_consumed) = OutPoint::from_bytes(bytes)?;
+        let mut offset = outpoint_consumed;
+
+        // Construct script signature using its from_byte method, starting from outpoint offset
+        let (script_sig, script_consumed) =
+            Script::from_bytes_with_options(&bytes[offset..], options)?;
+        offset += script_consumed;
+
+        // Read sequence from leftover bytes and calculate total_consumed_bytes
+        ensure_len(bytes, offset + 4)?;
+        let sequence = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let total_bytes_consumed = offset + 4;
+
+        // Create tx_input struct and return
+        let tx_input = TransactionInput {
+            previous_output: outpoint,
+            script_sig,
+            sequence,
+        };
+
+        Ok((tx_input, total_bytes_consumed))
+    }
+}
+
+// Hardening knobs for transaction parsing
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseOptions {
+    pub max_inputs: usize,
+    pub max_script_len: usize,
+    pub require_canonical_compact_size: bool,
+}
+
+impl ParseOptions {
+    // Limits matching consensus/standardness: 10,000-byte scripts, no more inputs than fit
+    // in a standard-size transaction (41 bytes minimum each), minimal CompactSize only
+    pub fn strict() -> Self {
+        ParseOptions {
+            max_inputs: MAX_STANDARD_TX_SIZE / 41,
+            max_script_len: 10_000,
+            require_canonical_compact_size: true,
+        }
+    }
+
+    // No limits: the behavior of plain from_bytes
+    pub fn permissive() -> Self {
+        ParseOptions {
+            max_inputs: usize::MAX,
+            max_script_len: usize::MAX,
+            require_canonical_compact_size: false,
+        }
+    }
+
+    // Read a CompactSize, rejecting non-minimal encodings when required
+    fn read_compact_size(&self, bytes: &[u8]) -> Result<(CompactSize, usize), BitcoinError> {
+        let (compact_size, consumed) = CompactSize::from_bytes(bytes)?;
+
+        if self.require_canonical_compact_size && compact_size.encoded_len() != consumed {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        Ok((compact_size, consumed))
+    }
+}
+
+// Spend type of an input, for signed-size estimation
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum InputType {
+    P2pkh,
+    P2wpkh,
+    // P2SH-wrapped P2WPKH (nested segwit)
+    P2shP2wpkh,
+    // Taproot key-path spend
+    P2tr,
+}
+
+impl InputType {
+    // Estimated (scriptSig length, witness bytes) once signed
+    fn signed_sizes(&self) -> (usize, usize) {
+        match self {
+            // <sig> <pubkey>
+            InputType::P2pkh => (1 + 72 + 1 + 33, 0),
+            // witness: item count, <sig>, <pubkey>
+            InputType::P2wpkh => (0, 1 + 1 + 72 + 1 + 33),
+            // scriptSig pushes the 22-byte redeem script
+            InputType::P2shP2wpkh => (1 + 22, 1 + 1 + 72 + 1 + 33),
+            // witness: item count, 64-byte Schnorr signature
+            InputType::P2tr => (0, 1 + 1 + 64),
+        }
+    }
+}
+
+// Sighash type flags appended to signature preimages
+pub const SIGHASH_ALL: u32 = 1;
+pub const SIGHASH_NONE: u32 = 2;
+pub const SIGHASH_SINGLE: u32 = 3;
+pub const SIGHASH_ANYONECANPAY: u32 = 0x80;
+
+// Which outputs a signature commits to, optionally only the signed input (ANYONECANPAY)
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SighashType {
+    All,
+    None,
+    Single,
+    AllPlusAnyoneCanPay,
+    NonePlusAnyoneCanPay,
+    SinglePlusAnyoneCanPay,
+}
+
+impl SighashType {
+    // Parse one of the six standard values; anything else is InvalidFormat
+    pub fn from_u32(value: u32) -> Result<Self, BitcoinError> {
+        let anyonecanpay = value & SIGHASH_ANYONECANPAY != 0;
+
+        match (value & !SIGHASH_ANYONECANPAY, anyonecanpay) {
+            (SIGHASH_ALL, false) => Ok(SighashType::All),
+            (SIGHASH_NONE, false) => Ok(SighashType::None),
+            (SIGHASH_SINGLE, false) => Ok(SighashType::Single),
+            (SIGHASH_ALL, true) => Ok(SighashType::AllPlusAnyoneCanPay),
+            (SIGHASH_NONE, true) => Ok(SighashType::NonePlusAnyoneCanPay),
+            (SIGHASH_SINGLE, true) => Ok(SighashType::SinglePlusAnyoneCanPay),
+            _ => Err(BitcoinError::InvalidFormat),
+        }
+    }
+
+    pub fn to_u32(&self) -> u32 {
+        match self {
+            SighashType::All => SIGHASH_ALL,
+            SighashType::None => SIGHASH_NONE,
+            SighashType::Single => SIGHASH_SINGLE,
+            SighashType::AllPlusAnyoneCanPay => SIGHASH_ALL | SIGHASH_ANYONECANPAY,
+            SighashType::NonePlusAnyoneCanPay => SIGHASH_NONE | SIGHASH_ANYONECANPAY,
+            SighashType::SinglePlusAnyoneCanPay => SIGHASH_SINGLE | SIGHASH_ANYONECANPAY,
         }
     }
+
+    pub fn is_anyonecanpay(&self) -> bool {
+        self.to_u32() & SIGHASH_ANYONECANPAY != 0
+    }
+}
+
+// lock_time values below this are block heights, at or above are UNIX timestamps
+const LOCKTIME_THRESHOLD: u32 = 500_000_000;
+
+// Sequence value that marks an input as final (disables lock_time for that input)
+const SEQUENCE_FINAL: u32 = 0xFFFFFFFF;
+
+// Weight units per non-witness byte (BIP-141)
+pub const WITNESS_SCALE_FACTOR: usize = 4;
+
+// Virtual size for a weight, rounding up to the next whole vbyte
+pub fn weight_to_vsize(weight: usize) -> usize {
+    weight.div_ceil(WITNESS_SCALE_FACTOR)
 }
 
+// Bitcoin Core's default minimum relay fee rate, in sat/kvB
+pub const DEFAULT_MIN_RELAY_TX_FEE: u64 = 1000;
+
+// Size bounds (in bytes) for a standard transaction
+const MIN_STANDARD_TX_SIZE: usize = 65;
+const MAX_STANDARD_TX_SIZE: usize = 100_000;
+
 #[derive(Debug, PartialEq, Eq, Clone, Serialize, Deserialize)]
 pub struct BitcoinTransaction {
     pub version: u32,
@@ -353,80 +1225,477 @@ impl BitcoinTransaction {
     // - lock_time (4 bytes LE)
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut btc_tx_bytes = Vec::new();
+        self.write_to(&mut btc_tx_bytes);
+        btc_tx_bytes
+    }
 
-        // Convert version to bytes and add to return vec
+    // Append the serialization to an existing buffer, so callers can reuse one allocation
+    pub fn write_to(&self, buf: &mut Vec<u8>) {
+        // Convert version to bytes and add to buffer
         let version_le = self.version.to_le_bytes();
-        btc_tx_bytes.extend(version_le);
+        buf.extend(version_le);
 
-        // Append size of inputs vec to return vec (bytes)
-        let input_len = self.inputs.len();
-        let input_size = CompactSize::new(input_len as u64).to_bytes();
-        btc_tx_bytes.extend(input_size);
+        // Append input count and serialized inputs
+        self.write_inputs(buf);
 
-        // Serialize each tx_input and append to return vec
-        for input in &self.inputs {
-            let serialized_input = input.to_bytes();
-            btc_tx_bytes.extend(serialized_input);
-        }
-
-        // Extend return vec with converted lock_time in bytes
+        // Extend buffer with converted lock_time in bytes
         let lock_time = self.lock_time.to_le_bytes();
-        btc_tx_bytes.extend(lock_time);
-
-        btc_tx_bytes
-    }
+        buf.extend(lock_time);
+    }
 
     // Read version, CompactSize for input count
     // Parse inputs one by one
     // Read final 4 bytes for lock_time
+    // A 0x00 input count is always read as zero inputs, never as a segwit marker: with no
+    // outputs modelled, an empty transaction is the 9 bytes version + 0x00 + lock_time.
     pub fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
-        let bytes_len = bytes.len();
+        Self::from_bytes_with_options(bytes, &ParseOptions::permissive())
+    }
 
-        if bytes.len() < 4 {
-            Err(BitcoinError::InsufficientBytes)
-        } else {
-            // Read version from bytes
-            let version = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
-            let mut offset = 4;
-
-            // Read CompactSize byte for input vector manipulation
-            let (compact_size, size_consumed) = CompactSize::from_bytes(&bytes[offset..])?;
-            let input_count = compact_size.value as usize;
-            offset += size_consumed;
-
-            // Parse and create transaction inputs
-            let mut inputs: Vec<TransactionInput> = vec![];
-            for _ in 0..input_count {
-                if bytes_len < offset {
-                    return Err(BitcoinError::InsufficientBytes);
-                }
+    // Same as from_bytes, rejecting input counts, script lengths or CompactSize encodings
+    // that the options disallow (InvalidFormat)
+    pub fn from_bytes_with_options(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Self, usize), BitcoinError> {
+        // Read version from bytes
+        ensure_len(bytes, 4)?;
+        let version = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+        let mut offset = 4;
+
+        // Parse input count and transaction inputs
+        let (inputs, inputs_consumed) = Self::parse_inputs_with_options(&bytes[offset..], options)?;
+        offset += inputs_consumed;
+
+        // Read lock_time
+        ensure_len(bytes, offset + 4)?;
+        let lock_time = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+        let total_bytes_consumed = offset + 4;
+
+        // Return formatted BitcoinTransaction
+        Ok((
+            BitcoinTransaction {
+                version,
+                inputs,
+                lock_time,
+            },
+            total_bytes_consumed,
+        ))
+    }
+
+    // Parse a legacy sighash preimage: the transaction followed by a 4-byte LE sighash type.
+    // Bytes beyond the sighash type are InvalidFormat.
+    pub fn from_sighash_preimage(bytes: &[u8]) -> Result<(Self, u32), BitcoinError> {
+        let (tx, consumed) = Self::from_bytes(bytes)?;
 
-                let (tx_input, input_size) = TransactionInput::from_bytes(&bytes[offset..])?;
-                inputs.push(tx_input);
-                offset += input_size;
+        ensure_len(bytes, consumed + 4)?;
+        if bytes.len() > consumed + 4 {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        let sighash_type = u32::from_le_bytes(bytes[consumed..].try_into().unwrap());
+        Ok((tx, sighash_type))
+    }
+
+    // Invariant check for fuzzing/tests: parse the bytes, re-serialize, and compare with
+    // the original buffer. Fails on parse errors, trailing bytes, or encodings (such as a
+    // non-canonical CompactSize) that don't survive the round trip byte for byte.
+    pub fn verify_roundtrip(bytes: &[u8]) -> bool {
+        match Self::from_bytes(bytes) {
+            Ok((parsed, consumed)) => consumed == bytes.len() && parsed.to_bytes() == bytes,
+            Err(_) => false,
+        }
+    }
+
+    // Serialization split into one "<hex>    <label>" line per wire field, for learning the
+    // format. An empty scriptSig has no line of its own, only its 00 length.
+    pub fn hex_dump_annotated(&self) -> String {
+        let mut lines = vec![
+            format!("{}    version", encode(self.version.to_le_bytes())),
+            format!(
+                "{}    input count",
+                encode(CompactSize::new(self.inputs.len() as u64).to_bytes())
+            ),
+        ];
+
+        for input in &self.inputs {
+            let script = &input.script_sig.bytes;
+            lines.push(format!(
+                "{}    prevout txid",
+                encode(input.previous_output.txid.0)
+            ));
+            lines.push(format!(
+                "{}    prevout vout",
+                encode(input.previous_output.vout.to_le_bytes())
+            ));
+            lines.push(format!(
+                "{}    scriptSig length",
+                encode(CompactSize::new(script.len() as u64).to_bytes())
+            ));
+            if !script.is_empty() {
+                lines.push(format!("{}    scriptSig", encode(script)));
             }
+            lines.push(format!(
+                "{}    sequence",
+                encode(input.sequence.to_le_bytes())
+            ));
+        }
 
-            // Read lock_time
-            if bytes_len < offset + 4 {
-                Err(BitcoinError::InsufficientBytes)
-            } else {
-                let lock_time = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
-                let total_bytes_consumed = offset + 4;
-
-                // Return formatted BitcoinTransaction
-                Ok((
-                    BitcoinTransaction {
-                        version,
-                        inputs,
-                        lock_time,
-                    },
-                    total_bytes_consumed,
-                ))
+        lines.push(format!(
+            "{}    lock_time",
+            encode(self.lock_time.to_le_bytes())
+        ));
+        lines.join("\n")
+    }
+
+    // Debugging aid for verify_roundtrip: the first byte where re-serializing the parsed
+    // transaction differs from the original bytes. A parse failure compares against an
+    // empty re-serialization.
+    pub fn roundtrip_diff(bytes: &[u8]) -> Option<(usize, u8, u8)> {
+        let reserialized = Self::from_bytes(bytes)
+            .map(|(parsed, _)| parsed.to_bytes())
+            .unwrap_or_default();
+
+        first_byte_diff(bytes, &reserialized)
+    }
+
+    // Serialize only the input vector: CompactSize (number of inputs) + each input
+    pub fn serialize_inputs(&self) -> Vec<u8> {
+        let mut inputs_bytes = Vec::new();
+        self.write_inputs(&mut inputs_bytes);
+        inputs_bytes
+    }
+
+    // Append the input count and each input to an existing buffer
+    fn write_inputs(&self, buf: &mut Vec<u8>) {
+        CompactSize::new(self.inputs.len() as u64).write_to(buf);
+
+        for input in &self.inputs {
+            input.write_to(buf);
+        }
+    }
+
+    // Parse a CompactSize input count followed by that many inputs
+    pub fn parse_inputs(bytes: &[u8]) -> Result<(Vec<TransactionInput>, usize), BitcoinError> {
+        Self::parse_inputs_with_options(bytes, &ParseOptions::permissive())
+    }
+
+    fn parse_inputs_with_options(
+        bytes: &[u8],
+        options: &ParseOptions,
+    ) -> Result<(Vec<TransactionInput>, usize), BitcoinError> {
+        // Read CompactSize byte for input vector manipulation
+        let (compact_size, size_consumed) = options.read_compact_size(bytes)?;
+        let input_count = compact_size.as_usize()?;
+        let mut offset = size_consumed;
+
+        if input_count > options.max_inputs {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        // Parse and create transaction inputs
+        let mut inputs: Vec<TransactionInput> = vec![];
+        for _ in 0..input_count {
+            let (tx_input, input_size) =
+                TransactionInput::from_bytes_with_options(&bytes[offset..], options)?;
+            inputs.push(tx_input);
+            offset += input_size;
+        }
+
+        Ok((inputs, offset))
+    }
+
+    // Heuristic check: true when there is at least one input and every input carries a
+    // non-empty scriptSig. Inputs have no witness field, so scriptSig is the only signal.
+    pub fn is_signed(&self) -> bool {
+        !self.inputs.is_empty() && self.unsigned_input_indices().is_empty()
+    }
+
+    // Clone with the input at `index` removed, keeping the others in order.
+    // An out-of-range index is InvalidFormat.
+    pub fn without_input(&self, index: usize) -> Result<BitcoinTransaction, BitcoinError> {
+        if index >= self.inputs.len() {
+            return Err(BitcoinError::InvalidFormat);
+        }
+
+        let mut tx = self.clone();
+        tx.inputs.remove(index);
+        Ok(tx)
+    }
+
+    // Indices of inputs that still need signing (empty scriptSig; there is no witness field)
+    pub fn unsigned_input_indices(&self) -> Vec<usize> {
+        self.inputs
+            .iter()
+            .enumerate()
+            .filter(|(_, input)| input.script_sig.bytes.is_empty())
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // A coinbase has exactly one input, spending the null outpoint
+    pub fn is_coinbase(&self) -> bool {
+        self.inputs.len() == 1 && self.inputs[0].previous_output.is_null()
+    }
+
+    // BIP-34 block height: the first push of the coinbase scriptSig as a script number.
+    // None for non-coinbase transactions or scriptSigs that don't start with a height.
+    // Pushes wider than 3 bytes (heights >= 2^23) are treated as pre-BIP-34 data, which
+    // rejects the 4-byte nBits push early coinbases started with.
+    pub fn coinbase_height(&self) -> Option<u32> {
+        if !self.is_coinbase() {
+            return None;
+        }
+
+        // Only the height push matters; extranonce and miner tags after it need not parse
+        let (instructions, _) = self.inputs[0].script_sig.parse_prefix();
+        let first = instructions.first()?;
+        let height = match (first.opcode, first.data) {
+            // Heights 1-16 are encoded as OP_1..OP_16
+            (0x51..=OP_16, None) => (first.opcode - 0x50) as i64,
+            (_, Some(data)) if !data.is_empty() && data.len() <= 3 => {
+                decode_script_num(data).ok()?
+            }
+            _ => return None,
+        };
+
+        u32::try_from(height).ok()
+    }
+
+    // Transaction ID: double SHA-256 of the serialized transaction, in internal byte order
+    pub fn compute_txid(&self) -> Txid {
+        self.compute_txid_with::<BuiltinSha256>()
+    }
+
+    // compute_txid using the given SHA-256 backend
+    pub fn compute_txid_with<H: Hasher256>(&self) -> Txid {
+        Txid(sha256d_with::<H>(&self.to_bytes()))
+    }
+
+    // Txid of a copy with every scriptSig emptied, so scriptSig-malleated variants share an
+    // id. Not a consensus identifier; only useful for tracking/deduplication.
+    pub fn normalized_txid(&self) -> Txid {
+        let mut normalized = self.clone();
+        for input in &mut normalized.inputs {
+            input.script_sig = Script::new(vec![]);
+        }
+        normalized.compute_txid()
+    }
+
+    // Hash once and return the txid hex in both internal and display (byte-reversed) order
+    pub fn txid_pair(&self) -> (String, String) {
+        let txid = self.compute_txid();
+        (encode(txid.0), txid.display_hex())
+    }
+
+    // BIP-143 hashPrevouts: double SHA-256 of every input's serialized outpoint.
+    // SIGHASH_ANYONECANPAY (which zeroes this) is left to the caller.
+    pub fn hash_prevouts(&self) -> [u8; 32] {
+        let mut data = Vec::with_capacity(self.inputs.len() * 36);
+        for input in &self.inputs {
+            data.extend(input.previous_output.to_bytes());
+        }
+        sha256d(&data)
+    }
+
+    // BIP-143 hashSequence: double SHA-256 of every input's 4-byte LE sequence
+    pub fn hash_sequence(&self) -> [u8; 32] {
+        let mut data = Vec::with_capacity(self.inputs.len() * 4);
+        for input in &self.inputs {
+            data.extend(input.sequence.to_le_bytes());
+        }
+        sha256d(&data)
+    }
+
+    // Human-readable summary of the absolute timelock. lock_time only applies when it is
+    // non-zero and at least one input has a non-final sequence (< 0xFFFFFFFF).
+    pub fn describe_timelock(&self) -> String {
+        let enabled = self
+            .inputs
+            .iter()
+            .any(|input| input.sequence < SEQUENCE_FINAL);
+
+        if self.lock_time == 0 || !enabled {
+            String::from("No timelock")
+        } else if self.lock_time < LOCKTIME_THRESHOLD {
+            format!("Locked until block {}", self.lock_time)
+        } else {
+            format!("Locked until timestamp {}", self.lock_time)
+        }
+    }
+
+    // Consensus finality (IsFinalTx): final if lock_time is 0, if lock_time is already
+    // satisfied by the given block height or time, or if every input's sequence is final
+    pub fn is_final(&self, height: u32, time: u32) -> bool {
+        if self.lock_time == 0 {
+            return true;
+        }
+
+        let threshold = if self.lock_time < LOCKTIME_THRESHOLD {
+            height
+        } else {
+            time
+        };
+        if self.lock_time < threshold {
+            return true;
+        }
+
+        self.inputs
+            .iter()
+            .all(|input| input.sequence == SEQUENCE_FINAL)
+    }
+
+    // Legacy (inaccurate) sigop count across all scriptSigs. Outputs aren't modelled, so
+    // scriptPubKey sigops are not included.
+    pub fn legacy_sigop_count(&self) -> usize {
+        self.inputs
+            .iter()
+            .map(|input| input.script_sig.sigop_count(false))
+            .sum()
+    }
+
+    // Sum of every input's serialized scriptSig size (prefix + bytes)
+    pub fn total_script_sig_size(&self) -> usize {
+        self.inputs
+            .iter()
+            .map(|input| input.script_sig_size())
+            .sum()
+    }
+
+    // Serialized size without witness data
+    pub fn base_size(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    // Serialized size including witness data; the same as base_size, as there is none
+    pub fn total_size(&self) -> usize {
+        self.to_bytes().len()
+    }
+
+    // BIP-141 weight: base size * 3 + total size
+    pub fn weight(&self) -> usize {
+        self.base_size() * (WITNESS_SCALE_FACTOR - 1) + self.total_size()
+    }
+
+    // Virtual size in vbytes. Transactions carry no witness data, so this equals the
+    // serialized size.
+    pub fn vsize(&self) -> usize {
+        weight_to_vsize(self.weight())
+    }
+
+    // Short table of the size metrics, one "label: value" row each
+    pub fn size_summary(&self) -> String {
+        let rows = [
+            ("Base size", self.base_size(), "bytes"),
+            ("Total size", self.total_size(), "bytes"),
+            ("Weight", self.weight(), "WU"),
+            ("Virtual size", self.vsize(), "vbytes"),
+            ("Inputs", self.inputs.len(), ""),
+            // Outputs are not modelled yet
+            ("Outputs", 0, ""),
+        ];
+
+        rows.iter()
+            .map(|(label, value, unit)| {
+                format!("{:<13} {} {}", format!("{}:", label), value, unit)
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    // Estimate the vsize once signed, replacing each input's scriptSig/witness with typical
+    // sizes for its type (72-byte signatures, 33-byte compressed keys). Inputs without a
+    // matching entry in input_types are counted as currently serialized.
+    pub fn estimate_signed_vsize(&self, input_types: &[InputType]) -> usize {
+        let mut base_size = self.to_bytes().len();
+        let mut witness_size = 0;
+        let mut witness_inputs = 0;
+
+        for (input, input_type) in self.inputs.iter().zip(input_types) {
+            let (script_sig_len, input_witness_size) = input_type.signed_sizes();
+
+            base_size -= input.script_sig_size();
+            base_size += CompactSize::new(script_sig_len as u64).encoded_len() + script_sig_len;
+
+            if input_witness_size > 0 {
+                witness_size += input_witness_size;
+                witness_inputs += 1;
             }
         }
+
+        if witness_inputs > 0 {
+            // Marker + flag, and a 0x00 item count for every input without witness data
+            witness_size += 2 + (self.inputs.len() - witness_inputs);
+        }
+
+        let weight = base_size * WITNESS_SCALE_FACTOR + witness_size;
+        weight_to_vsize(weight)
+    }
+
+    // Serialized bytes added by one more input with a script_len-byte scriptSig and a
+    // witness_size-byte witness (item count included, 0 for none), including any growth of
+    // the input-count CompactSize
+    pub fn size_delta_add_input(&self, script_len: usize, witness_size: usize) -> usize {
+        let (base_delta, witness_delta) = self.add_input_deltas(script_len, witness_size);
+        base_delta + witness_delta
+    }
+
+    // Weight units added by one more input; see size_delta_add_input
+    pub fn weight_delta_add_input(&self, script_len: usize, witness_size: usize) -> usize {
+        let (base_delta, witness_delta) = self.add_input_deltas(script_len, witness_size);
+        base_delta * WITNESS_SCALE_FACTOR + witness_delta
+    }
+
+    // (non-witness bytes, witness bytes) added by one more input
+    fn add_input_deltas(&self, script_len: usize, witness_size: usize) -> (usize, usize) {
+        let count = self.inputs.len() as u64;
+        let count_growth =
+            CompactSize::new(count + 1).encoded_len() - CompactSize::new(count).encoded_len();
+
+        let script_prefix = CompactSize::new(script_len as u64).encoded_len();
+        let base_delta = 36 + script_prefix + script_len + 4 + count_growth;
+
+        // The first witness adds marker + flag and an empty witness for every existing input
+        let witness_delta = if witness_size > 0 {
+            witness_size + 2 + self.inputs.len()
+        } else {
+            0
+        };
+
+        (base_delta, witness_delta)
+    }
+
+    // Smallest fee (in satoshis) that reaches the target fee rate: ceil(vsize * rate)
+    pub fn min_fee_for_rate(&self, rate_sat_vb: f64) -> u64 {
+        (self.vsize() as f64 * rate_sat_vb).ceil() as u64
+    }
+
+    // Minimum fee (in satoshis) for relay at a rate in sat/kvB: ceil(vsize * rate / 1000).
+    // Bitcoin Core's default rate is DEFAULT_MIN_RELAY_TX_FEE. Absurd rates saturate.
+    pub fn min_relay_fee(&self, rate_sat_kvb: u64) -> u64 {
+        (self.vsize() as u64)
+            .saturating_mul(rate_sat_kvb)
+            .div_ceil(1000)
+    }
+
+    // Standard relay size check: at least 65 bytes (smaller non-witness transactions are
+    // non-standard since 2018) and no more than 100,000 bytes
+    pub fn is_standard_size(&self) -> bool {
+        (MIN_STANDARD_TX_SIZE..=MAX_STANDARD_TX_SIZE).contains(&self.to_bytes().len())
+    }
+
+    // Serialize the transaction and wrap it in a `tx` P2P message for the given network magic
+    pub fn to_p2p_tx_message(&self, magic: [u8; 4]) -> Vec<u8> {
+        // "tx" is always a valid command, so framing can only fail on a >4 GiB payload
+        frame_message(magic, "tx", &self.to_bytes()).expect("transaction payload too large")
     }
 }
 
+// Scripts longer than this are truncated by the alternate ({:#}) Display
+const DISPLAY_SCRIPT_MAX_BYTES: usize = 32;
+
 impl Display for BitcoinTransaction {
     // Format a user-friendly string showing version, inputs, lock_time
     fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
@@ -461,11 +1730,18 @@ impl Display for BitcoinTransaction {
             writeln!(f, "  ScriptSig Length: {}\n", input.script_sig.bytes.len())?;
 
             // Write "  ScriptSig Bytes: " + hex(script_sig.bytes)
-            writeln!(
-                f,
-                "  ScriptSig Bytes: {}\n",
-                encode(&input.script_sig.bytes)
-            )?;
+            // With {:#}, long scripts are elided to a prefix and their byte count
+            let script_bytes = &input.script_sig.bytes;
+            if f.alternate() && script_bytes.len() > DISPLAY_SCRIPT_MAX_BYTES {
+                writeln!(
+                    f,
+                    "  ScriptSig Bytes: {}…({} bytes)\n",
+                    encode(&script_bytes[..DISPLAY_SCRIPT_MAX_BYTES]),
+                    script_bytes.len()
+                )?;
+            } else {
+                writeln!(f, "  ScriptSig Bytes: {}\n", encode(script_bytes))?;
+            }
 
             // Write "  Sequence: " + sequence
             writeln!(f, "  Sequence: {}\n", input.sequence)?;
@@ -478,3 +1754,833 @@ impl Display for BitcoinTransaction {
         Ok(())
     }
 }
+
+// Read-only view of a transaction input whose scriptSig borrows from the parsed buffer
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BorrowedInput<'a> {
+    pub previous_output: OutPoint,
+    pub script_sig: &'a [u8],
+    pub sequence: u32,
+}
+
+// Zero-copy transaction view for scanning: same layout as BitcoinTransaction, but the
+// scripts are slices into the input bytes instead of owned vectors
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BorrowedTransaction<'a> {
+    pub version: u32,
+    pub inputs: Vec<BorrowedInput<'a>>,
+    pub lock_time: u32,
+}
+
+impl<'a> BorrowedTransaction<'a> {
+    // Parse like BitcoinTransaction::from_bytes, returning the view and bytes consumed
+    pub fn from_bytes_borrowed(bytes: &'a [u8]) -> Result<(Self, usize), BitcoinError> {
+        ensure_len(bytes, 4)?;
+        let version = u32::from_le_bytes(bytes[0..4].try_into().unwrap());
+
+        let (compact_size, size_consumed) = CompactSize::from_bytes(&bytes[4..])?;
+        let input_count = compact_size.as_usize()?;
+        let mut offset = 4 + size_consumed;
+
+        let mut inputs = Vec::new();
+        for _ in 0..input_count {
+            let (previous_output, outpoint_consumed) = OutPoint::from_bytes(&bytes[offset..])?;
+            offset += outpoint_consumed;
+
+            let (script_size, script_size_consumed) = CompactSize::from_bytes(&bytes[offset..])?;
+            offset += script_size_consumed;
+            let script_len = script_size.as_usize()?;
+            ensure_len(&bytes[offset..], script_len)?;
+            let script_sig = &bytes[offset..offset + script_len];
+            offset += script_len;
+
+            ensure_len(bytes, offset + 4)?;
+            let sequence = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+            offset += 4;
+
+            inputs.push(BorrowedInput {
+                previous_output,
+                script_sig,
+                sequence,
+            });
+        }
+
+        ensure_len(bytes, offset + 4)?;
+        let lock_time = u32::from_le_bytes(bytes[offset..offset + 4].try_into().unwrap());
+
+        Ok((
+            BorrowedTransaction {
+                version,
+                inputs,
+                lock_time,
+            },
+            offset + 4,
+        ))
+    }
+
+    // Copy the scripts out into an owned BitcoinTransaction
+    pub fn to_transaction(&self) -> BitcoinTransaction {
+        let inputs = self
+            .inputs
+            .iter()
+            .map(|input| {
+                TransactionInput::new(
+                    input.previous_output.clone(),
+                    Script::new(input.script_sig.to_vec()),
+                    input.sequence,
+                )
+            })
+            .collect();
+
+        BitcoinTransaction::new(self.version, inputs, self.lock_time)
+    }
+}
+
+// Half of the secp256k1 curve order, big-endian
+const SECP256K1_HALF_ORDER: [u8; 32] = [
+    0x7f, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff, 0xff,
+    0x5d, 0x57, 0x6e, 0x73, 0x57, 0xa4, 0x50, 0x1d, 0xdf, 0xe9, 0x2f, 0x46, 0x68, 0x1b, 0x20, 0xa0,
+];
+
+// BIP-62 low-S check on a DER signature (optionally followed by a sighash byte): true when
+// S <= n/2. Malformed DER returns false.
+pub fn is_low_s(sig: &[u8]) -> bool {
+    // 0x30 <len> 0x02 <r_len> <r> 0x02 <s_len> <s>
+    if sig.len() < 8 || sig[0] != 0x30 {
+        return false;
+    }
+    let seq_len = sig[1] as usize;
+    if sig.len() != seq_len + 2 && sig.len() != seq_len + 3 {
+        return false;
+    }
+
+    let r_len = sig[3] as usize;
+    if sig[2] != 0x02 || 4 + r_len + 2 > seq_len + 2 {
+        return false;
+    }
+
+    let s_offset = 4 + r_len;
+    let s_len = sig[s_offset + 1] as usize;
+    if sig[s_offset] != 0x02 || s_offset + 2 + s_len != seq_len + 2 {
+        return false;
+    }
+
+    // Drop DER's leading zero padding, then compare as a 32-byte big-endian number
+    let s_bytes = &sig[s_offset + 2..s_offset + 2 + s_len];
+    let first_nonzero = s_bytes.iter().position(|&b| b != 0).unwrap_or(s_len);
+    let s_value = &s_bytes[first_nonzero..];
+    if s_value.len() > 32 {
+        return false;
+    }
+
+    let mut s_padded = [0u8; 32];
+    s_padded[32 - s_value.len()..].copy_from_slice(s_value);
+    s_padded <= SECP256K1_HALF_ORDER
+}
+
+// Check for the BIP-174 PSBT magic "psbt" + 0xFF
+pub fn is_psbt(bytes: &[u8]) -> bool {
+    bytes.starts_with(&[0x70, 0x73, 0x62, 0x74, 0xff])
+}
+
+// Detect the segwit marker (0x00) and flag (0x01) right after the 4-byte version.
+// Ambiguity: a legacy transaction with zero inputs also has 0x00 at offset 4, so a
+// zero-input legacy transaction followed by a 0x01 byte is indistinguishable here.
+pub fn has_witness_marker(bytes: &[u8]) -> bool {
+    bytes.len() >= 6 && bytes[4] == 0x00 && bytes[5] == 0x01
+}
+
+// First offset where two buffers differ, with the byte from each. If one buffer is a
+// prefix of the other, the offset is the shorter length and the missing byte reads 0x00.
+pub fn first_byte_diff(a: &[u8], b: &[u8]) -> Option<(usize, u8, u8)> {
+    if let Some(offset) = a.iter().zip(b).position(|(x, y)| x != y) {
+        return Some((offset, a[offset], b[offset]));
+    }
+
+    let offset = a.len().min(b.len());
+    if a.len() == b.len() {
+        None
+    } else {
+        Some((
+            offset,
+            a.get(offset).copied().unwrap_or(0),
+            b.get(offset).copied().unwrap_or(0),
+        ))
+    }
+}
+
+// Decode a hex transaction and render it with Display. Invalid hex or trailing bytes
+// after the transaction are InvalidFormat.
+pub fn decode_and_display(hex: &str) -> Result<String, BitcoinError> {
+    let bytes = decode(hex.trim()).map_err(|_| BitcoinError::InvalidFormat)?;
+    let (tx, consumed) = BitcoinTransaction::from_bytes(&bytes)?;
+
+    if consumed != bytes.len() {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    Ok(tx.to_string())
+}
+
+// Serialize a block body: CompactSize (number of transactions) + each transaction
+pub fn serialize_block_body(txs: &[BitcoinTransaction]) -> Vec<u8> {
+    let mut body_bytes = CompactSize::new(txs.len() as u64).to_bytes();
+
+    for tx in txs {
+        body_bytes.extend(tx.to_bytes());
+    }
+
+    body_bytes
+}
+
+// Parse a CompactSize transaction count followed by that many transactions
+pub fn parse_block_body(bytes: &[u8]) -> Result<(Vec<BitcoinTransaction>, usize), BitcoinError> {
+    let (compact_size, size_consumed) = CompactSize::from_bytes(bytes)?;
+    let tx_count = compact_size.as_usize()?;
+    let mut offset = size_consumed;
+
+    let mut txs = vec![];
+    for _ in 0..tx_count {
+        let (tx, tx_size) = BitcoinTransaction::from_bytes(&bytes[offset..])?;
+        txs.push(tx);
+        offset += tx_size;
+    }
+
+    Ok((txs, offset))
+}
+
+// Object-safe serialization, so heterogeneous types can live behind `dyn BitcoinSerializeDyn`
+pub trait BitcoinSerializeDyn {
+    fn to_bytes(&self) -> Vec<u8>;
+}
+
+// Parsing half of the interface; returns the value and number of bytes consumed.
+// Kept separate because returning Self is not object-safe.
+pub trait BitcoinDeserialize: Sized {
+    fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError>;
+}
+
+// Forward both traits to the inherent to_bytes/from_bytes methods
+macro_rules! impl_bitcoin_serialize {
+    ($($ty:ty),*) => {
+        $(
+            impl BitcoinSerializeDyn for $ty {
+                fn to_bytes(&self) -> Vec<u8> {
+                    <$ty>::to_bytes(self)
+                }
+            }
+
+            impl BitcoinDeserialize for $ty {
+                fn from_bytes(bytes: &[u8]) -> Result<(Self, usize), BitcoinError> {
+                    <$ty>::from_bytes(bytes)
+                }
+            }
+        )*
+    };
+}
+
+impl_bitcoin_serialize!(
+    CompactSize,
+    OutPoint,
+    Script,
+    TransactionInput,
+    BitcoinTransaction
+);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct P2pMessage {
+    pub magic: [u8; 4],
+    pub command: String,
+    pub payload: Vec<u8>,
+}
+
+// Wrap a payload in the P2P message header:
+// - magic (4 bytes)
+// - command (12 bytes, ASCII, NUL-padded)
+// - payload length (4 bytes LE)
+// - checksum (first 4 bytes of sha256d(payload))
+pub fn frame_message(
+    magic: [u8; 4],
+    command: &str,
+    payload: &[u8],
+) -> Result<Vec<u8>, BitcoinError> {
+    // Commands are 1 to 12 printable ASCII characters
+    if command.is_empty() || command.len() > 12 || !command.bytes().all(|b| b.is_ascii_graphic()) {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let payload_len = u32::try_from(payload.len()).map_err(|_| BitcoinError::InvalidFormat)?;
+
+    let mut message = Vec::with_capacity(24 + payload.len());
+    message.extend(magic);
+
+    let mut command_bytes = [0u8; 12];
+    command_bytes[..command.len()].copy_from_slice(command.as_bytes());
+    message.extend(command_bytes);
+
+    message.extend(payload_len.to_le_bytes());
+    message.extend(&sha256d(payload)[..4]);
+    message.extend(payload);
+
+    Ok(message)
+}
+
+// Parse a framed P2P message, verifying the command padding and payload checksum.
+// Returns the message and the number of bytes consumed.
+pub fn parse_message(bytes: &[u8]) -> Result<(P2pMessage, usize), BitcoinError> {
+    ensure_len(bytes, 24)?;
+
+    let magic: [u8; 4] = bytes[0..4].try_into().unwrap();
+
+    // Command runs up to the first NUL; everything after it must be padding
+    let command_bytes = &bytes[4..16];
+    let command_len = command_bytes.iter().position(|&b| b == 0).unwrap_or(12);
+    if command_len == 0
+        || !command_bytes[command_len..].iter().all(|&b| b == 0)
+        || !command_bytes[..command_len]
+            .iter()
+            .all(|b| b.is_ascii_graphic())
+    {
+        return Err(BitcoinError::InvalidFormat);
+    }
+    let command = String::from_utf8(command_bytes[..command_len].to_vec()).unwrap();
+
+    let payload_len = u32::from_le_bytes(bytes[16..20].try_into().unwrap()) as usize;
+    let checksum = &bytes[20..24];
+
+    let total_len = payload_len
+        .checked_add(24)
+        .ok_or(BitcoinError::InsufficientBytes)?;
+    ensure_len(bytes, total_len)?;
+
+    let payload = &bytes[24..total_len];
+    if sha256d(payload)[..4] != *checksum {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    Ok((
+        P2pMessage {
+            magic,
+            command,
+            payload: payload.to_vec(),
+        },
+        total_len,
+    ))
+}
+
+// Bitcoin network, selecting address prefixes and P2P magic
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Network {
+    Mainnet,
+    Testnet,
+    Regtest,
+    Signet,
+}
+
+impl Network {
+    // Base58Check version byte for P2PKH addresses
+    pub fn p2pkh_version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x00,
+            Network::Testnet | Network::Regtest | Network::Signet => 0x6f,
+        }
+    }
+
+    // Base58Check version byte for P2SH addresses
+    pub fn p2sh_version(&self) -> u8 {
+        match self {
+            Network::Mainnet => 0x05,
+            Network::Testnet | Network::Regtest | Network::Signet => 0xc4,
+        }
+    }
+
+    // Human-readable part of segwit addresses
+    pub fn bech32_hrp(&self) -> &'static str {
+        match self {
+            Network::Mainnet => "bc",
+            Network::Testnet | Network::Signet => "tb",
+            Network::Regtest => "bcrt",
+        }
+    }
+
+    // P2P message start bytes (default signet challenge for Signet)
+    pub fn magic(&self) -> [u8; 4] {
+        match self {
+            Network::Mainnet => [0xf9, 0xbe, 0xb4, 0xd9],
+            Network::Testnet => [0x0b, 0x11, 0x09, 0x07],
+            Network::Regtest => [0xfa, 0xbf, 0xb5, 0xda],
+            Network::Signet => [0x0a, 0x03, 0xcf, 0x40],
+        }
+    }
+}
+
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+// Base58Check: version byte + payload + first 4 bytes of sha256d, in base 58.
+// Each leading zero byte becomes a leading '1'.
+fn base58check_encode(version: u8, payload: &[u8]) -> String {
+    let mut data = vec![version];
+    data.extend_from_slice(payload);
+    let checksum = sha256d(&data);
+    data.extend_from_slice(&checksum[..4]);
+
+    // Repeated division of the big-endian number by 58, digits kept little-endian
+    let mut digits: Vec<u8> = Vec::new();
+    for &byte in &data {
+        let mut carry = byte as u32;
+        for digit in digits.iter_mut() {
+            carry += (*digit as u32) << 8;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.push((carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+
+    let leading_zeros = data.iter().take_while(|&&b| b == 0).count();
+    let mut encoded = "1".repeat(leading_zeros);
+    encoded.extend(
+        digits
+            .iter()
+            .rev()
+            .map(|&d| BASE58_ALPHABET[d as usize] as char),
+    );
+    encoded
+}
+
+// Inverse of base58check_encode, returning the version byte and payload. Characters
+// outside the alphabet or a checksum mismatch are InvalidFormat.
+fn base58check_decode(encoded: &str) -> Result<(u8, Vec<u8>), BitcoinError> {
+    // Repeated multiplication by 58, bytes kept little-endian
+    let mut bytes: Vec<u8> = Vec::new();
+    for c in encoded.bytes() {
+        let mut carry = BASE58_ALPHABET
+            .iter()
+            .position(|&a| a == c)
+            .ok_or(BitcoinError::InvalidFormat)? as u32;
+        for byte in bytes.iter_mut() {
+            carry += (*byte as u32) * 58;
+            *byte = (carry & 0xff) as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.push((carry & 0xff) as u8);
+            carry >>= 8;
+        }
+    }
+
+    let leading_ones = encoded.bytes().take_while(|&c| c == b'1').count();
+    let mut data = vec![0u8; leading_ones];
+    data.extend(bytes.iter().rev());
+
+    if data.len() < 5 {
+        return Err(BitcoinError::InvalidFormat);
+    }
+    let (body, checksum) = data.split_at(data.len() - 4);
+    if sha256d(body)[..4] != *checksum {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    Ok((body[0], body[1..].to_vec()))
+}
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+// Checksum constants: BIP-173 bech32 (witness v0) and BIP-350 bech32m (v1+)
+const BECH32_CONST: u32 = 1;
+const BECH32M_CONST: u32 = 0x2bc830a3;
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+
+    let mut chk: u32 = 1;
+    for &value in values {
+        let top = chk >> 25;
+        chk = ((chk & 0x1ffffff) << 5) ^ value as u32;
+        for (i, g) in GENERATOR.iter().enumerate() {
+            if (top >> i) & 1 == 1 {
+                chk ^= g;
+            }
+        }
+    }
+    chk
+}
+
+// HRP expanded for checksumming: high bits of each char, a separator 0, then the low bits
+fn bech32_hrp_expand(hrp: &str) -> Vec<u8> {
+    let mut expanded: Vec<u8> = hrp.bytes().map(|c| c >> 5).collect();
+    expanded.push(0);
+    expanded.extend(hrp.bytes().map(|c| c & 0x1f));
+    expanded
+}
+
+// Regroup 8-bit bytes into 5-bit values, zero-padding the last group
+fn to_base32(data: &[u8]) -> Vec<u8> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut out = Vec::with_capacity((data.len() * 8).div_ceil(5));
+
+    for &byte in data {
+        acc = (acc << 8) | byte as u32;
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(((acc >> bits) & 0x1f) as u8);
+        }
+    }
+    if bits > 0 {
+        out.push(((acc << (5 - bits)) & 0x1f) as u8);
+    }
+    out
+}
+
+// Segwit address: witness version + program in base32, with a bech32 checksum for v0
+// and bech32m for later versions
+fn segwit_encode(hrp: &str, witness_version: u8, program: &[u8]) -> String {
+    let mut data = vec![witness_version];
+    data.extend(to_base32(program));
+
+    let checksum_const = if witness_version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend(&data);
+    values.extend([0u8; 6]);
+    let polymod = bech32_polymod(&values) ^ checksum_const;
+    data.extend((0..6).map(|i| ((polymod >> (5 * (5 - i))) & 0x1f) as u8));
+
+    let mut address = format!("{}1", hrp);
+    address.extend(data.iter().map(|&d| BECH32_CHARSET[d as usize] as char));
+    address
+}
+
+// Inverse of segwit_encode for the expected HRP, returning the witness version and
+// program. Mixed case, a wrong HRP, a bad checksum, non-zero padding or a program length
+// outside BIP-141's rules are InvalidFormat.
+fn segwit_decode(hrp: &str, address: &str) -> Result<(u8, Vec<u8>), BitcoinError> {
+    if address.bytes().any(|c| c.is_ascii_lowercase())
+        && address.bytes().any(|c| c.is_ascii_uppercase())
+    {
+        return Err(BitcoinError::InvalidFormat);
+    }
+    let address = address.to_ascii_lowercase();
+
+    let separator = address.rfind('1').ok_or(BitcoinError::InvalidFormat)?;
+    if &address[..separator] != hrp {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let data = address[separator + 1..]
+        .bytes()
+        .map(|c| {
+            BECH32_CHARSET
+                .iter()
+                .position(|&a| a == c)
+                .map(|d| d as u8)
+                .ok_or(BitcoinError::InvalidFormat)
+        })
+        .collect::<Result<Vec<u8>, BitcoinError>>()?;
+    if data.len() < 7 {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let witness_version = data[0];
+    let checksum_const = if witness_version == 0 {
+        BECH32_CONST
+    } else {
+        BECH32M_CONST
+    };
+    let mut values = bech32_hrp_expand(hrp);
+    values.extend(&data);
+    if witness_version > 16 || bech32_polymod(&values) != checksum_const {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let program = from_base32(&data[1..data.len() - 6])?;
+    if program.len() < 2
+        || program.len() > 40
+        || (witness_version == 0 && program.len() != 20 && program.len() != 32)
+    {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    Ok((witness_version, program))
+}
+
+// Regroup 5-bit values into bytes; leftover padding must be under 5 bits and all zero
+fn from_base32(data: &[u8]) -> Result<Vec<u8>, BitcoinError> {
+    let mut acc: u32 = 0;
+    let mut bits = 0;
+    let mut out = Vec::with_capacity(data.len() * 5 / 8);
+
+    for &value in data {
+        acc = (acc << 5) | value as u32;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push(((acc >> bits) & 0xff) as u8);
+        }
+    }
+    if bits >= 5 || (acc & ((1 << bits) - 1)) != 0 {
+        return Err(BitcoinError::InvalidFormat);
+    }
+    Ok(out)
+}
+
+// Decode an address for the given network into its scriptPubKey. Addresses for another
+// network, unknown version bytes and malformed encodings are InvalidFormat.
+pub fn address_to_script(address: &str, network: Network) -> Result<Script, BitcoinError> {
+    let hrp = network.bech32_hrp();
+    let segwit_prefix = format!("{}1", hrp);
+
+    if address.to_ascii_lowercase().starts_with(&segwit_prefix) {
+        let (witness_version, program) = segwit_decode(hrp, address)?;
+
+        // OP_0 for v0, OP_1..OP_16 otherwise, then a direct push of the program
+        let mut bytes = vec![if witness_version == 0 {
+            0x00
+        } else {
+            0x50 + witness_version
+        }];
+        bytes.push(program.len() as u8);
+        bytes.extend(program);
+        return Ok(Script::new(bytes));
+    }
+
+    let (version, payload) = base58check_decode(address)?;
+    if payload.len() != 20 {
+        return Err(BitcoinError::InvalidFormat);
+    }
+
+    let mut bytes = Vec::with_capacity(25);
+    if version == network.p2pkh_version() {
+        bytes.extend([0x76, 0xa9, 0x14]);
+        bytes.extend(payload);
+        bytes.extend([0x88, 0xac]);
+    } else if version == network.p2sh_version() {
+        bytes.extend([0xa9, 0x14]);
+        bytes.extend(payload);
+        bytes.push(0x87);
+    } else {
+        return Err(BitcoinError::InvalidFormat);
+    }
+    Ok(Script::new(bytes))
+}
+
+const SHA256_K: [u32; 64] = [
+    0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4, 0xab1c5ed5,
+    0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe, 0x9bdc06a7, 0xc19bf174,
+    0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f, 0x4a7484aa, 0x5cb0a9dc, 0x76f988da,
+    0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7, 0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967,
+    0x27b70a85, 0x2e1b2138, 0x4d2c6dfc, 0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85,
+    0xa2bfe8a1, 0xa81a664b, 0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070,
+    0x19a4c116, 0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+    0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7, 0xc67178f2,
+];
+
+// SHA-256 as specified in FIPS 180-4
+pub fn sha256(data: &[u8]) -> [u8; 32] {
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+
+    // Pad with 0x80, zeros, then the message length in bits (big-endian u64)
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_be_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().unwrap());
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16]
+                .wrapping_add(s0)
+                .wrapping_add(w[i - 7])
+                .wrapping_add(s1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let temp1 = h
+                .wrapping_add(s1)
+                .wrapping_add(ch)
+                .wrapping_add(SHA256_K[i])
+                .wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let temp2 = s0.wrapping_add(maj);
+
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(temp1);
+            d = c;
+            c = b;
+            b = a;
+            a = temp1.wrapping_add(temp2);
+        }
+
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0u8; 32];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+// Double SHA-256, used for txids and P2P checksums
+pub fn sha256d(data: &[u8]) -> [u8; 32] {
+    sha256d_with::<BuiltinSha256>(data)
+}
+
+// A SHA-256 implementation, so callers can plug in hardware or third-party backends
+pub trait Hasher256 {
+    fn hash(data: &[u8]) -> [u8; 32];
+}
+
+// The crate's own software SHA-256
+pub struct BuiltinSha256;
+
+impl Hasher256 for BuiltinSha256 {
+    fn hash(data: &[u8]) -> [u8; 32] {
+        sha256(data)
+    }
+}
+
+// Double SHA-256 using the given backend
+pub fn sha256d_with<H: Hasher256>(data: &[u8]) -> [u8; 32] {
+    H::hash(&H::hash(data))
+}
+
+// BIP-340 tagged hash: SHA256(SHA256(tag) || SHA256(tag) || msg), used throughout taproot
+pub fn tagged_hash(tag: &str, msg: &[u8]) -> [u8; 32] {
+    let tag_hash = sha256(tag.as_bytes());
+
+    let mut data = Vec::with_capacity(64 + msg.len());
+    data.extend_from_slice(&tag_hash);
+    data.extend_from_slice(&tag_hash);
+    data.extend_from_slice(msg);
+    sha256(&data)
+}
+
+// HASH160: RIPEMD-160 of SHA-256, used for P2PKH/P2SH hashes
+pub fn hash160(data: &[u8]) -> [u8; 20] {
+    ripemd160(&sha256(data))
+}
+
+// Message word selection, rotation amounts and constants for the left and right lines
+const RIPEMD160_R: [usize; 80] = [
+    0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 7, 4, 13, 1, 10, 6, 15, 3, 12, 0, 9, 5,
+    2, 14, 11, 8, 3, 10, 14, 4, 9, 15, 8, 1, 2, 7, 0, 6, 13, 11, 5, 12, 1, 9, 11, 10, 0, 8, 12, 4,
+    13, 3, 7, 15, 14, 5, 6, 2, 4, 0, 5, 9, 7, 12, 2, 10, 14, 1, 3, 8, 11, 6, 15, 13,
+];
+const RIPEMD160_R_PRIME: [usize; 80] = [
+    5, 14, 7, 0, 9, 2, 11, 4, 13, 6, 15, 8, 1, 10, 3, 12, 6, 11, 3, 7, 0, 13, 5, 10, 14, 15, 8, 12,
+    4, 9, 1, 2, 15, 5, 1, 3, 7, 14, 6, 9, 11, 8, 12, 2, 10, 0, 4, 13, 8, 6, 4, 1, 3, 11, 15, 0, 5,
+    12, 2, 13, 9, 7, 10, 14, 12, 15, 10, 4, 1, 5, 8, 7, 6, 2, 13, 14, 0, 3, 9, 11,
+];
+const RIPEMD160_S: [u32; 80] = [
+    11, 14, 15, 12, 5, 8, 7, 9, 11, 13, 14, 15, 6, 7, 9, 8, 7, 6, 8, 13, 11, 9, 7, 15, 7, 12, 15,
+    9, 11, 7, 13, 12, 11, 13, 6, 7, 14, 9, 13, 15, 14, 8, 13, 6, 5, 12, 7, 5, 11, 12, 14, 15, 14,
+    15, 9, 8, 9, 14, 5, 6, 8, 6, 5, 12, 9, 15, 5, 11, 6, 8, 13, 12, 5, 12, 13, 14, 11, 8, 5, 6,
+];
+const RIPEMD160_S_PRIME: [u32; 80] = [
+    8, 9, 9, 11, 13, 15, 15, 5, 7, 7, 8, 11, 14, 14, 12, 6, 9, 13, 15, 7, 12, 8, 9, 11, 7, 7, 12,
+    7, 6, 15, 13, 11, 9, 7, 15, 11, 8, 6, 6, 14, 12, 13, 5, 14, 13, 13, 7, 5, 15, 5, 8, 11, 14, 14,
+    6, 14, 6, 9, 12, 9, 12, 5, 15, 8, 8, 5, 12, 9, 12, 5, 14, 6, 8, 13, 6, 5, 15, 13, 11, 11,
+];
+const RIPEMD160_K: [u32; 5] = [0x00000000, 0x5a827999, 0x6ed9eba1, 0x8f1bbcdc, 0xa953fd4e];
+const RIPEMD160_K_PRIME: [u32; 5] = [0x50a28be6, 0x5c4dd124, 0x6d703ef3, 0x7a6d76e9, 0x00000000];
+
+// RIPEMD-160 as specified by Dobbertin, Bosselaers and Preneel
+fn ripemd160(data: &[u8]) -> [u8; 20] {
+    // Boolean function for round j (0..5)
+    fn f(j: usize, x: u32, y: u32, z: u32) -> u32 {
+        match j {
+            0 => x ^ y ^ z,
+            1 => (x & y) | (!x & z),
+            2 => (x | !y) ^ z,
+            3 => (x & z) | (y & !z),
+            _ => x ^ (y | !z),
+        }
+    }
+
+    let mut state: [u32; 5] = [0x67452301, 0xefcdab89, 0x98badcfe, 0x10325476, 0xc3d2e1f0];
+
+    // Same padding as SHA-256 but with a little-endian bit length
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&((data.len() as u64) * 8).to_le_bytes());
+
+    for block in message.chunks_exact(64) {
+        let mut x = [0u32; 16];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            x[i] = u32::from_le_bytes(word.try_into().unwrap());
+        }
+
+        let [mut al, mut bl, mut cl, mut dl, mut el] = state;
+        let [mut ar, mut br, mut cr, mut dr, mut er] = state;
+
+        for j in 0..80 {
+            let round = j / 16;
+
+            let t = al
+                .wrapping_add(f(round, bl, cl, dl))
+                .wrapping_add(x[RIPEMD160_R[j]])
+                .wrapping_add(RIPEMD160_K[round])
+                .rotate_left(RIPEMD160_S[j])
+                .wrapping_add(el);
+            al = el;
+            el = dl;
+            dl = cl.rotate_left(10);
+            cl = bl;
+            bl = t;
+
+            let t = ar
+                .wrapping_add(f(4 - round, br, cr, dr))
+                .wrapping_add(x[RIPEMD160_R_PRIME[j]])
+                .wrapping_add(RIPEMD160_K_PRIME[round])
+                .rotate_left(RIPEMD160_S_PRIME[j])
+                .wrapping_add(er);
+            ar = er;
+            er = dr;
+            dr = cr.rotate_left(10);
+            cr = br;
+            br = t;
+        }
+
+        let t = state[1].wrapping_add(cl).wrapping_add(dr);
+        state[1] = state[2].wrapping_add(dl).wrapping_add(er);
+        state[2] = state[3].wrapping_add(el).wrapping_add(ar);
+        state[3] = state[4].wrapping_add(al).wrapping_add(br);
+        state[4] = state[0].wrapping_add(bl).wrapping_add(cr);
+        state[0] = t;
+    }
+
+    let mut digest = [0u8; 20];
+    for (chunk, word) in digest.chunks_exact_mut(4).zip(state) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    digest
+}
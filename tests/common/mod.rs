@@ -0,0 +1,138 @@
+// Test-only serde harness. No binary serde format is available offline, so this is a
+// minimal non-human-readable format that only understands raw bytes.
+
+use serde::de::Visitor;
+use serde::de::value::Error as ValueError;
+use serde::ser::Impossible;
+use serde::{Deserializer, Serialize, Serializer};
+
+pub struct BinarySerializer;
+
+macro_rules! unsupported {
+    ($($method:ident($($ty:ty),*)),*) => {
+        $(fn $method(self, $(_: $ty),*) -> Result<Vec<u8>, ValueError> {
+            Err(serde::ser::Error::custom("unsupported"))
+        })*
+    };
+}
+
+impl Serializer for BinarySerializer {
+    type Ok = Vec<u8>;
+    type Error = ValueError;
+    type SerializeSeq = Impossible<Vec<u8>, ValueError>;
+    type SerializeTuple = Impossible<Vec<u8>, ValueError>;
+    type SerializeTupleStruct = Impossible<Vec<u8>, ValueError>;
+    type SerializeTupleVariant = Impossible<Vec<u8>, ValueError>;
+    type SerializeMap = Impossible<Vec<u8>, ValueError>;
+    type SerializeStruct = Impossible<Vec<u8>, ValueError>;
+    type SerializeStructVariant = Impossible<Vec<u8>, ValueError>;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn serialize_bytes(self, v: &[u8]) -> Result<Vec<u8>, ValueError> {
+        Ok(v.to_vec())
+    }
+
+    unsupported!(
+        serialize_bool(bool),
+        serialize_i8(i8),
+        serialize_i16(i16),
+        serialize_i32(i32),
+        serialize_i64(i64),
+        serialize_u8(u8),
+        serialize_u16(u16),
+        serialize_u32(u32),
+        serialize_u64(u64),
+        serialize_f32(f32),
+        serialize_f64(f64),
+        serialize_char(char),
+        serialize_str(&str),
+        serialize_none(),
+        serialize_unit(),
+        serialize_unit_struct(&'static str),
+        serialize_unit_variant(&'static str, u32, &'static str)
+    );
+
+    fn serialize_some<T: ?Sized + Serialize>(self, _: &T) -> Result<Vec<u8>, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_newtype_struct<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_newtype_variant<T: ?Sized + Serialize>(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: &T,
+    ) -> Result<Vec<u8>, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleStruct, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_tuple_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeTupleVariant, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_struct(
+        self,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStruct, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+    fn serialize_struct_variant(
+        self,
+        _: &'static str,
+        _: u32,
+        _: &'static str,
+        _: usize,
+    ) -> Result<Self::SerializeStructVariant, ValueError> {
+        Err(serde::ser::Error::custom("unsupported"))
+    }
+}
+
+pub struct BinaryDeserializer<'a>(pub &'a [u8]);
+
+impl<'de> Deserializer<'de> for BinaryDeserializer<'de> {
+    type Error = ValueError;
+
+    fn is_human_readable(&self) -> bool {
+        false
+    }
+
+    fn deserialize_any<V: Visitor<'de>>(self, visitor: V) -> Result<V::Value, ValueError> {
+        visitor.visit_borrowed_bytes(self.0)
+    }
+
+    serde::forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+        bytes byte_buf option unit unit_struct newtype_struct seq tuple
+        tuple_struct map struct enum identifier ignored_any
+    }
+}
@@ -1,5 +1,8 @@
+use hex::encode;
 use rust_week_3_exercises::*;
 
+mod common;
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -118,4 +121,2178 @@ mod tests {
         assert!(output.contains("Lock Time: 0"));
         assert!(output.contains("Previous Output Vout: 7"));
     }
+
+    #[test]
+    fn test_bitcoin_tx_is_signed() {
+        let unsigned = BitcoinTransaction::new(
+            2,
+            vec![
+                TransactionInput::new(OutPoint::new(dummy_txid(1), 0), Script::new(vec![]), 0),
+                TransactionInput::new(OutPoint::new(dummy_txid(2), 1), Script::new(vec![]), 0),
+            ],
+            0,
+        );
+        assert!(!unsigned.is_signed());
+
+        let mut signed = unsigned.clone();
+        for input in &mut signed.inputs {
+            input.script_sig = Script::new(vec![0x01, 0x02]);
+        }
+        assert!(signed.is_signed());
+
+        let mut half_signed = unsigned.clone();
+        half_signed.inputs[0].script_sig = Script::new(vec![0x01]);
+        assert!(!half_signed.is_signed());
+    }
+
+    #[test]
+    fn test_compact_size_roundtrip_property() {
+        let mut values = vec![
+            0u64,
+            1,
+            252,
+            253,
+            65535,
+            65536,
+            4294967295,
+            4294967296,
+            u64::MAX - 1,
+            u64::MAX,
+        ];
+
+        // xorshift64 keeps the "random" values deterministic across runs
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        for _ in 0..1000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            // Shift by a varying amount so every encoding width gets exercised
+            values.push(state >> (state % 64));
+        }
+
+        for value in values {
+            let bytes = CompactSize::new(value).to_bytes();
+            let expected_len = match value {
+                0..=252 => 1,
+                253..=65535 => 3,
+                65536..=4294967295 => 5,
+                _ => 9,
+            };
+            assert_eq!(bytes.len(), expected_len, "value {}", value);
+
+            let (decoded, consumed) = CompactSize::from_bytes(&bytes).unwrap();
+            assert_eq!(decoded.value, value);
+            assert_eq!(consumed, expected_len);
+        }
+    }
+
+    #[test]
+    fn test_txid_prefix_matching() {
+        let txid = Txid(dummy_txid(0xEE));
+        assert!(txid.matches_prefix(&[]));
+        assert!(txid.matches_prefix(&[0x00, 0x00]));
+        assert!(txid.matches_prefix(&txid.0));
+        assert!(!txid.matches_prefix(&[0x01]));
+        assert!(!txid.matches_prefix(&[0u8; 33]));
+
+        let padded = Txid::from_prefix(&[0xAB, 0xCD]);
+        assert_eq!(padded.0[..2], [0xAB, 0xCD]);
+        assert!(padded.0[2..].iter().all(|&b| b == 0));
+        assert!(padded.matches_prefix(&[0xAB, 0xCD]));
+
+        let truncated = Txid::from_prefix(&[0x11; 40]);
+        assert_eq!(truncated, Txid([0x11; 32]));
+    }
+
+    #[test]
+    fn test_p2p_message_framing() {
+        let magic = [0xF9, 0xBE, 0xB4, 0xD9];
+        let tx = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        let payload = tx.to_bytes();
+
+        let framed = frame_message(magic, "tx", &payload).unwrap();
+        assert_eq!(framed.len(), 24 + payload.len());
+        assert_eq!(framed[0..4], magic);
+        assert_eq!(framed[4..16], *b"tx\0\0\0\0\0\0\0\0\0\0");
+        assert_eq!(framed[16..20], (payload.len() as u32).to_le_bytes());
+
+        let (message, consumed) = parse_message(&framed).unwrap();
+        assert_eq!(consumed, framed.len());
+        assert_eq!(message.magic, magic);
+        assert_eq!(message.command, "tx");
+        let (parsed, _) = BitcoinTransaction::from_bytes(&message.payload).unwrap();
+        assert_eq!(parsed, tx);
+
+        // Empty payload checksum is the well-known 0x5df6e0e2 (e.g. verack)
+        let verack = frame_message(magic, "verack", &[]).unwrap();
+        assert_eq!(verack[20..24], [0x5D, 0xF6, 0xE0, 0xE2]);
+
+        let mut corrupted = framed.clone();
+        *corrupted.last_mut().unwrap() ^= 0xFF;
+        assert_eq!(parse_message(&corrupted), Err(BitcoinError::InvalidFormat));
+        assert_eq!(
+            parse_message(&framed[..framed.len() - 1]),
+            Err(BitcoinError::InsufficientBytes)
+        );
+        assert_eq!(
+            frame_message(magic, "thirteenchars", &[]),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        // An empty command is rejected on both sides
+        assert_eq!(
+            frame_message(magic, "", &[]),
+            Err(BitcoinError::InvalidFormat)
+        );
+        let mut empty_command = verack.clone();
+        empty_command[4..16].fill(0);
+        assert_eq!(
+            parse_message(&empty_command),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        // A u32::MAX payload length is reported as missing bytes, not an overflow
+        let mut huge = verack.clone();
+        huge[16..20].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert_eq!(parse_message(&huge), Err(BitcoinError::InsufficientBytes));
+    }
+
+    #[test]
+    fn test_bitcoin_tx_p2p_message() {
+        let magic = [0x0B, 0x11, 0x09, 0x07];
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(3), 1),
+                Script::new(vec![0xAA, 0xBB, 0xCC]),
+                0xFFFFFFFE,
+            )],
+            500,
+        );
+
+        let framed = tx.to_p2p_tx_message(magic);
+        assert_eq!(framed, frame_message(magic, "tx", &tx.to_bytes()).unwrap());
+
+        let (message, consumed) = parse_message(&framed).unwrap();
+        assert_eq!(consumed, framed.len());
+        assert_eq!(message.magic, magic);
+        assert_eq!(message.command, "tx");
+
+        let (parsed, _) = BitcoinTransaction::from_bytes(&message.payload).unwrap();
+        assert_eq!(parsed, tx);
+    }
+
+    #[test]
+    fn test_hash_functions() {
+        assert_eq!(
+            encode(sha256(b"abc")),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        assert_eq!(
+            encode(sha256d(b"abc")),
+            "4f8b42c22dd3729b519ba6f68d2da7cc5b2d606d05daed5ad5128cc03e6c6358"
+        );
+        assert_eq!(
+            encode(hash160(b"abc")),
+            "bb1be98c142444d7a56aa3981c3942a978e4dc33"
+        );
+
+        // Multi-block inputs exercise the padding across 64-byte boundaries
+        assert_eq!(
+            encode(sha256(&[b'a'; 1000])),
+            "41edece42d63e8d9bf515a9ba6932e1c20cbc9f5a5d134645adb5db1b9737ea3"
+        );
+        assert_eq!(
+            encode(hash160(&[b'a'; 1000])),
+            "7c2b902bbfae7c54f2498f50bc78fa46c0693802"
+        );
+        assert_eq!(
+            encode(hash160(b"")),
+            "b472a266d0bd89c13706a4132ccfb16f7c3b9fcb"
+        );
+    }
+
+    #[test]
+    fn test_inputs_serialization_roundtrip() {
+        let inputs = vec![
+            TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            ),
+            TransactionInput::new(
+                OutPoint::new(dummy_txid(2), 5),
+                Script::new(vec![0x03, 0x04, 0x05]),
+                0xFFFFFFFD,
+            ),
+        ];
+        let tx = BitcoinTransaction::new(2, inputs.clone(), 0);
+
+        let bytes = tx.serialize_inputs();
+        assert_eq!(bytes[0], 0x02);
+        assert_eq!(
+            bytes[1..],
+            [inputs[0].to_bytes(), inputs[1].to_bytes()].concat()
+        );
+
+        let (parsed, consumed) = BitcoinTransaction::parse_inputs(&bytes).unwrap();
+        assert_eq!(parsed, inputs);
+        assert_eq!(consumed, bytes.len());
+
+        assert_eq!(
+            BitcoinTransaction::parse_inputs(&bytes[..bytes.len() - 1]),
+            Err(BitcoinError::InsufficientBytes)
+        );
+    }
+
+    #[test]
+    fn test_compact_size_ordering_and_width() {
+        let mut values = vec![
+            CompactSize::new(65536),
+            CompactSize::new(0),
+            CompactSize::new(u64::MAX),
+            CompactSize::new(253),
+        ];
+        values.sort();
+        assert_eq!(
+            values,
+            vec![
+                CompactSize::new(0),
+                CompactSize::new(253),
+                CompactSize::new(65536),
+                CompactSize::new(u64::MAX),
+            ]
+        );
+        assert!(CompactSize::new(252) < CompactSize::new(253));
+
+        assert_eq!(CompactSize::new(252).encoded_len(), 1);
+        assert_eq!(CompactSize::new(65535).encoded_len(), 3);
+        assert_eq!(CompactSize::new(4294967295).encoded_len(), 5);
+        assert_eq!(CompactSize::new(u64::MAX).encoded_len(), 9);
+
+        assert!(CompactSize::new(0).same_encoded_width(&CompactSize::new(252)));
+        assert!(!CompactSize::new(252).same_encoded_width(&CompactSize::new(253)));
+        assert!(CompactSize::new(253).same_encoded_width(&CompactSize::new(65535)));
+        assert!(!CompactSize::new(65535).same_encoded_width(&CompactSize::new(65536)));
+    }
+
+    #[test]
+    fn test_outpoint_from_display_txid() {
+        // Input of the block 170 transaction (first P2P payment) spending the block 9 coinbase
+        let outpoint = OutPoint::from_display_txid(
+            "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9",
+            0,
+        )
+        .unwrap();
+        assert_eq!(
+            encode(outpoint.to_bytes()),
+            "c997a5e56e104102fa209c6a852dd90660a20b2d9c352423edce25857fcd3704\
+             00000000"
+        );
+
+        assert_eq!(
+            OutPoint::from_display_txid("zz", 0),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            OutPoint::from_display_txid("abcd", 0),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        // Accepts the same 0x-prefixed form as Txid::from_str
+        let display = "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9";
+        let prefixed = OutPoint::from_display_txid(&format!("0x{}", display), 0).unwrap();
+        assert_eq!(prefixed, outpoint);
+        assert_eq!(prefixed.txid, display.parse::<Txid>().unwrap());
+    }
+
+    #[test]
+    fn test_bitcoin_tx_txid_pair() {
+        let tx = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+
+        let (internal, display) = tx.txid_pair();
+        assert_eq!(
+            internal,
+            "0a086bd11514dac7f7288361d6e2b769af3b32212da86c7d0751b2352bd11e9a"
+        );
+        assert_eq!(
+            display,
+            "9a1ed12b35b251077d6ca82d21323baf69b7e2d6618328f7c7da1415d16b080a"
+        );
+        assert_eq!(encode(tx.compute_txid().0), internal);
+    }
+
+    #[test]
+    fn test_script_is_push_only() {
+        // <sig> <pubkey> style scriptSig, plus OP_0 and OP_PUSHDATA1
+        let mut script_sig = vec![0x00, 0x03, 0xAA, 0xBB, 0xCC, 0x4C, 0x02, 0x01, 0x02];
+        script_sig.extend([0x51, 0x60]);
+        assert!(Script::new(script_sig).is_push_only());
+        assert!(Script::new(vec![]).is_push_only());
+
+        // OP_DUP is not a push
+        assert!(!Script::new(vec![0x02, 0x01, 0x02, 0x76]).is_push_only());
+
+        // A push claiming more bytes than remain is malformed
+        assert!(!Script::new(vec![0x05, 0x01]).is_push_only());
+        assert!(!Script::new(vec![0x4C]).is_push_only());
+    }
+
+    #[test]
+    fn test_bitcoin_tx_describe_timelock() {
+        let input = |sequence| {
+            TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![]),
+                sequence,
+            )
+        };
+
+        let by_height = BitcoinTransaction::new(2, vec![input(0xFFFFFFFE)], 800000);
+        assert_eq!(by_height.describe_timelock(), "Locked until block 800000");
+
+        let by_time = BitcoinTransaction::new(2, vec![input(0xFFFFFFFE)], 1700000000);
+        assert_eq!(
+            by_time.describe_timelock(),
+            "Locked until timestamp 1700000000"
+        );
+
+        // All-final sequences disable lock_time entirely
+        let disabled = BitcoinTransaction::new(2, vec![input(0xFFFFFFFF)], 800000);
+        assert_eq!(disabled.describe_timelock(), "No timelock");
+
+        let zero = BitcoinTransaction::new(2, vec![input(0)], 0);
+        assert_eq!(zero.describe_timelock(), "No timelock");
+    }
+
+    #[test]
+    fn test_txid_deserialize_hex_prefix() {
+        let hex_str = "00000000000000000000000000000000000000000000000000000000000000ab";
+        let plain: Txid = serde_json::from_str(&format!("\"{}\"", hex_str)).unwrap();
+        let lower: Txid = serde_json::from_str(&format!("\"0x{}\"", hex_str)).unwrap();
+        let upper: Txid = serde_json::from_str(&format!("\"0X{}\"", hex_str)).unwrap();
+
+        assert_eq!(plain, Txid(dummy_txid(0xAB)));
+        assert_eq!(lower, plain);
+        assert_eq!(upper, plain);
+    }
+
+    #[test]
+    fn test_txid_deserialize_rejects_invalid() {
+        // Non-hex, wrong JSON type, bare prefix and wrong length error rather than panic
+        assert!(serde_json::from_str::<Txid>("\"zz\"").is_err());
+        assert!(serde_json::from_str::<Txid>("5").is_err());
+        assert!(serde_json::from_str::<Txid>("\"0x\"").is_err());
+        assert!(serde_json::from_str::<Txid>("\"abcd\"").is_err());
+    }
+
+    #[test]
+    fn test_script_num_roundtrip() {
+        let cases: Vec<(i64, Vec<u8>)> = vec![
+            (0, vec![]),
+            (1, vec![0x01]),
+            (-1, vec![0x81]),
+            (127, vec![0x7F]),
+            (-127, vec![0xFF]),
+            (128, vec![0x80, 0x00]),
+            (-128, vec![0x80, 0x80]),
+            (255, vec![0xFF, 0x00]),
+            (256, vec![0x00, 0x01]),
+            (-32768, vec![0x00, 0x80, 0x80]),
+            (
+                i64::MAX,
+                vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F],
+            ),
+            (
+                -i64::MAX,
+                vec![0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF],
+            ),
+        ];
+
+        for (n, bytes) in cases {
+            assert_eq!(encode_script_num(n), bytes, "encode {}", n);
+            assert_eq!(decode_script_num(&bytes), Ok(n), "decode {}", n);
+        }
+
+        // i64::MIN is out of range: its magnitude fills 8 bytes, so the sign needs a 9th
+        let min = encode_script_num(i64::MIN);
+        assert_eq!(min, vec![0, 0, 0, 0, 0, 0, 0, 0x80, 0x80]);
+        assert_eq!(decode_script_num(&min), Err(BitcoinError::InvalidFormat));
+
+        // Negative zero decodes to 0
+        assert_eq!(decode_script_num(&[0x80]), Ok(0));
+        assert_eq!(
+            decode_script_num(&[0x01; 9]),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_tx_is_standard_size() {
+        let tx_with_script = |script_len| {
+            BitcoinTransaction::new(
+                2,
+                vec![TransactionInput::new(
+                    OutPoint::new(dummy_txid(1), 0),
+                    Script::new(vec![0x51; script_len]),
+                    0xFFFFFFFF,
+                )],
+                0,
+            )
+        };
+
+        // 4 + 1 + (36 + 1 + 107 + 4) + 4 = 157 bytes
+        let normal = tx_with_script(107);
+        assert!(normal.is_standard_size());
+
+        // Too small: 50 bytes with an empty scriptSig
+        assert!(!tx_with_script(0).is_standard_size());
+
+        let too_large = tx_with_script(100_000);
+        assert!(too_large.to_bytes().len() > 100_000);
+        assert!(!too_large.is_standard_size());
+    }
+
+    #[test]
+    fn test_script_accessors() {
+        let script = Script::new(vec![0x76, 0xA9, 0x14]);
+        assert_eq!(script.as_bytes(), &[0x76, 0xA9, 0x14]);
+        assert_eq!(script.as_bytes().as_ptr(), script.bytes.as_ptr());
+        assert_eq!(script.len(), 3);
+        assert!(!script.is_empty());
+
+        let empty = Script::new(vec![]);
+        assert_eq!(empty.as_bytes(), &[] as &[u8]);
+        assert_eq!(empty.len(), 0);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_bitcoin_tx_is_final() {
+        let input = |sequence| {
+            TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![]),
+                sequence,
+            )
+        };
+
+        // lock_time 0 is always final
+        let unlocked = BitcoinTransaction::new(2, vec![input(0)], 0);
+        assert!(unlocked.is_final(0, 0));
+
+        // Height lock is satisfied only once the height is strictly greater
+        let height_locked = BitcoinTransaction::new(2, vec![input(0)], 800000);
+        assert!(!height_locked.is_final(800000, u32::MAX));
+        assert!(height_locked.is_final(800001, 0));
+
+        // Time lock compares against time, not height
+        let time_locked = BitcoinTransaction::new(2, vec![input(0)], 1700000000);
+        assert!(!time_locked.is_final(u32::MAX, 1700000000));
+        assert!(time_locked.is_final(0, 1700000001));
+
+        // All-final sequences override an unsatisfied lock_time
+        let final_sequences = BitcoinTransaction::new(2, vec![input(0xFFFFFFFF)], 800000);
+        assert!(final_sequences.is_final(0, 0));
+    }
+
+    #[test]
+    fn test_bitcoin_transaction_alternate_display() {
+        let input = TransactionInput::new(
+            OutPoint::new(dummy_txid(0xCD), 0),
+            Script::new(vec![0xAB; 120]),
+            0xFFFFFFFF,
+        );
+        let tx = BitcoinTransaction::new(1, vec![input], 0);
+
+        let normal = format!("{}", tx);
+        assert!(normal.contains(&"ab".repeat(120)));
+        assert!(!normal.contains("(120 bytes)"));
+
+        let compact = format!("{:#}", tx);
+        let expected = format!("  ScriptSig Bytes: {}…(120 bytes)", "ab".repeat(32));
+        assert!(compact.contains(&expected));
+        assert!(!compact.contains(&"ab".repeat(33)));
+        assert!(compact.contains("Previous Output Vout: 0"));
+
+        // Short scripts are printed in full either way
+        let short = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0,
+            )],
+            0,
+        );
+        assert_eq!(format!("{:#}", short), format!("{}", short));
+    }
+
+    #[test]
+    fn test_dyn_serialization() {
+        let outpoint = OutPoint::new(dummy_txid(7), 1);
+        let objects: Vec<Box<dyn BitcoinSerializeDyn>> =
+            vec![Box::new(CompactSize::new(300)), Box::new(outpoint.clone())];
+
+        let serialized: Vec<Vec<u8>> = objects.iter().map(|obj| obj.to_bytes()).collect();
+        assert_eq!(serialized[0], vec![0xFD, 0x2C, 0x01]);
+        assert_eq!(serialized[1], outpoint.to_bytes());
+
+        fn parse<T: BitcoinDeserialize>(bytes: &[u8]) -> T {
+            T::from_bytes(bytes).unwrap().0
+        }
+        assert_eq!(parse::<CompactSize>(&serialized[0]), CompactSize::new(300));
+        assert_eq!(parse::<OutPoint>(&serialized[1]), outpoint);
+    }
+
+    #[test]
+    fn test_bitcoin_tx_min_fee_for_rate() {
+        // 4 + 1 + (36 + 1 + 107 + 4) + 4 = 157 vbytes
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x51; 107]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        assert_eq!(tx.vsize(), 157);
+
+        assert_eq!(tx.min_fee_for_rate(1.0), 157);
+        // 157 * 2.5 = 392.5 rounds up
+        assert_eq!(tx.min_fee_for_rate(2.5), 393);
+        assert_eq!(tx.min_fee_for_rate(0.0), 0);
+    }
+
+    #[test]
+    fn test_block_body_roundtrip() {
+        let txs = vec![
+            BitcoinTransaction::new(
+                1,
+                vec![TransactionInput::new(
+                    OutPoint::new([0u8; 32], 0xFFFFFFFF),
+                    Script::new(vec![0x03, 0x80, 0x1A, 0x06]),
+                    0xFFFFFFFF,
+                )],
+                0,
+            ),
+            BitcoinTransaction::new(
+                2,
+                vec![TransactionInput::new(
+                    OutPoint::new(dummy_txid(9), 3),
+                    Script::new(vec![0x01, 0x02]),
+                    0xFFFFFFFE,
+                )],
+                100,
+            ),
+        ];
+
+        let body = serialize_block_body(&txs);
+        assert_eq!(body[0], 0x02);
+        assert_eq!(body[1..], [txs[0].to_bytes(), txs[1].to_bytes()].concat());
+
+        let (parsed, consumed) = parse_block_body(&body).unwrap();
+        assert_eq!(parsed, txs);
+        assert_eq!(consumed, body.len());
+
+        assert_eq!(
+            parse_block_body(&body[..body.len() - 1]),
+            Err(BitcoinError::InsufficientBytes)
+        );
+    }
+
+    #[test]
+    fn test_compact_size_range_boundaries() {
+        assert_eq!(COMPACT_U8_MAX, 252);
+        assert_eq!(COMPACT_U16_MAX, 65535);
+        assert_eq!(COMPACT_U32_MAX, 4294967295);
+
+        // Each boundary value uses its own width and the next value steps up a width
+        let boundaries = [
+            (COMPACT_U8_MAX, 1, 3),
+            (COMPACT_U16_MAX, 3, 5),
+            (COMPACT_U32_MAX, 5, 9),
+        ];
+        for (max, width, next_width) in boundaries {
+            assert_eq!(CompactSize::new(max).to_bytes().len(), width);
+            assert_eq!(CompactSize::new(max).encoded_len(), width);
+            assert_eq!(CompactSize::new(max + 1).to_bytes().len(), next_width);
+            assert_eq!(CompactSize::new(max + 1).encoded_len(), next_width);
+        }
+
+        assert_eq!(CompactSize::new(COMPACT_U8_MAX).to_bytes(), vec![0xFC]);
+        assert_eq!(CompactSize::new(COMPACT_U8_MAX + 1).to_bytes()[0], 0xFD);
+        assert_eq!(CompactSize::new(COMPACT_U16_MAX).to_bytes()[0], 0xFD);
+        assert_eq!(CompactSize::new(COMPACT_U16_MAX + 1).to_bytes()[0], 0xFE);
+        assert_eq!(CompactSize::new(COMPACT_U32_MAX).to_bytes()[0], 0xFE);
+        assert_eq!(CompactSize::new(COMPACT_U32_MAX + 1).to_bytes()[0], 0xFF);
+        assert_eq!(CompactSize::new(u64::MAX).to_bytes()[0], 0xFF);
+    }
+
+    #[test]
+    fn test_bitcoin_tx_normalized_txid() {
+        let tx_with_script = |script: Vec<u8>| {
+            BitcoinTransaction::new(
+                2,
+                vec![TransactionInput::new(
+                    OutPoint::new(dummy_txid(1), 0),
+                    Script::new(script),
+                    0xFFFFFFFF,
+                )],
+                0,
+            )
+        };
+
+        // Same spend with a different (malleated) scriptSig encoding
+        let original = tx_with_script(vec![0x01, 0xAA]);
+        let malleated = tx_with_script(vec![0x4C, 0x01, 0xAA]);
+
+        assert_ne!(original.compute_txid(), malleated.compute_txid());
+        assert_eq!(original.normalized_txid(), malleated.normalized_txid());
+        assert_eq!(
+            original.normalized_txid(),
+            tx_with_script(vec![]).compute_txid()
+        );
+
+        // Changes outside scriptSig still change the normalized id
+        let mut other = original.clone();
+        other.lock_time = 1;
+        assert_ne!(other.normalized_txid(), original.normalized_txid());
+    }
+
+    #[test]
+    fn test_bitcoin_tx_parse_options() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        let canonical = tx.to_bytes();
+
+        // Re-encode the input count (1) non-canonically as 0xFD 0x01 0x00
+        let mut non_canonical = canonical[..4].to_vec();
+        non_canonical.extend([0xFD, 0x01, 0x00]);
+        non_canonical.extend(&canonical[5..]);
+
+        let (parsed, consumed) = BitcoinTransaction::from_bytes_with_options(
+            &non_canonical,
+            &ParseOptions::permissive(),
+        )
+        .unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(consumed, non_canonical.len());
+        assert_eq!(
+            BitcoinTransaction::from_bytes_with_options(&non_canonical, &ParseOptions::strict()),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            BitcoinTransaction::from_bytes_with_options(&canonical, &ParseOptions::strict()),
+            Ok((tx.clone(), canonical.len()))
+        );
+
+        let limited = ParseOptions {
+            max_inputs: 0,
+            ..ParseOptions::permissive()
+        };
+        assert_eq!(
+            BitcoinTransaction::from_bytes_with_options(&canonical, &limited),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        let short_scripts = ParseOptions {
+            max_script_len: 1,
+            ..ParseOptions::permissive()
+        };
+        assert_eq!(
+            BitcoinTransaction::from_bytes_with_options(&canonical, &short_scripts),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_script_asm_annotated() {
+        let mut p2pkh = vec![0x76, 0xA9, 0x14];
+        p2pkh.extend([0x11; 20]);
+        p2pkh.extend([0x88, 0xAC]);
+        let script = Script::new(p2pkh);
+
+        let expected_asm = format!(
+            "OP_DUP OP_HASH160 {} OP_EQUALVERIFY OP_CHECKSIG",
+            "11".repeat(20)
+        );
+        assert_eq!(script.to_asm(), expected_asm);
+        assert_eq!(
+            script.to_asm_annotated(),
+            format!("{} // P2PKH spend", expected_asm)
+        );
+
+        // Unrecognized scripts are disassembled without a note
+        let custom = Script::new(vec![0x00, 0x51, 0x02, 0xAB, 0xCD, 0x93]);
+        assert_eq!(custom.to_asm(), "OP_0 OP_1 abcd OP_ADD");
+        assert_eq!(custom.to_asm_annotated(), custom.to_asm());
+
+        assert_eq!(Script::new(vec![0x4C, 0x05, 0x01]).to_asm(), "[error]");
+    }
+
+    #[test]
+    fn test_script_to_bytes_prefix_widths() {
+        let cases: [(usize, &[u8]); 8] = [
+            (0, &[0x00]),
+            (1, &[0x01]),
+            (10, &[0x0a]),
+            (252, &[0xfc]),
+            (253, &[0xfd, 0xfd, 0x00]),
+            (300, &[0xfd, 0x2c, 0x01]),
+            (65535, &[0xfd, 0xff, 0xff]),
+            (70000, &[0xfe, 0x70, 0x11, 0x01, 0x00]),
+        ];
+
+        for (len, prefix) in cases {
+            let script = Script::new((0..len).map(|i| i as u8).collect());
+            let bytes = script.to_bytes();
+
+            assert_eq!(&bytes[..prefix.len()], prefix);
+            assert_eq!(bytes[prefix.len()..], script.bytes[..]);
+            assert_eq!(bytes.len(), prefix.len() + len);
+            // Room for prefix and script is reserved up front
+            assert!(bytes.capacity() >= prefix.len() + len);
+        }
+    }
+
+    #[test]
+    fn test_txid_conversions() {
+        let raw = dummy_txid(0x42);
+        assert_eq!(Txid::from(raw), Txid(raw));
+
+        let digest = sha256d(b"abc");
+        assert_eq!(Txid::try_from(&digest[..]), Ok(Txid(digest)));
+
+        assert_eq!(
+            Txid::try_from(&digest[..31]),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            Txid::try_from(&[0u8; 33][..]),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(Txid::try_from(&[][..]), Err(BitcoinError::InvalidFormat));
+    }
+
+    #[test]
+    fn test_has_witness_marker() {
+        let legacy = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            0,
+        )
+        .to_bytes();
+        assert!(!has_witness_marker(&legacy));
+
+        // Segwit serialization: version, marker 0x00, flag 0x01, then the input count
+        let mut segwit = legacy[..4].to_vec();
+        segwit.extend([0x00, 0x01]);
+        segwit.extend(&legacy[4..]);
+        assert!(has_witness_marker(&segwit));
+
+        assert!(!has_witness_marker(&segwit[..5]));
+        assert!(!has_witness_marker(&[]));
+    }
+
+    #[test]
+    fn test_script_empty_roundtrip() {
+        let empty = Script::new(vec![]);
+        assert_eq!(empty.to_bytes(), vec![0x00]);
+
+        let (parsed, consumed) = Script::from_bytes(&[0x00]).unwrap();
+        assert_eq!(parsed, empty);
+        assert_eq!(consumed, 1);
+
+        // Trailing bytes after a zero-length script are left for the caller
+        let (parsed, consumed) = Script::from_bytes(&[0x00, 0xFF, 0xFF]).unwrap();
+        assert!(parsed.bytes.is_empty());
+        assert_eq!(consumed, 1);
+
+        // An unsigned input with an empty scriptSig round-trips through the full input
+        let input = TransactionInput::new(OutPoint::new(dummy_txid(1), 0), empty, 0xFFFFFFFF);
+        let bytes = input.to_bytes();
+        assert_eq!(bytes.len(), 36 + 1 + 4);
+        assert_eq!(TransactionInput::from_bytes(&bytes), Ok((input, 41)));
+    }
+
+    #[test]
+    fn test_is_low_s() {
+        // Signature from the block 170 transaction (with SIGHASH_ALL byte)
+        let low_s = hex::decode(
+            "304402204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd41\
+             0220181522ec8eca07de4860a4acdd12909d831cc56cbbac4622082221a8768d1d0901",
+        )
+        .unwrap();
+        assert!(is_low_s(&low_s));
+        assert!(is_low_s(&low_s[..low_s.len() - 1]));
+
+        // Same signature with S replaced by n - S
+        let high_s = hex::decode(
+            "304502204e45e16932b8af514961a1d3a1a25fdf3f4f7732e9d624c6c61548ab5fb8cd41\
+             022100e7eadd137135f821b79f5b5322ed6f6137921779f39c5a19b7b03ce459a9243801",
+        )
+        .unwrap();
+        assert!(!is_low_s(&high_s));
+
+        // S exactly n/2 is still low; n/2 + 1 is high
+        let with_s = |s: &str| {
+            let s = hex::decode(s).unwrap();
+            let mut sig = vec![
+                0x30,
+                (5 + s.len()) as u8,
+                0x02,
+                0x01,
+                0x01,
+                0x02,
+                s.len() as u8,
+            ];
+            sig.extend(s);
+            sig
+        };
+        assert!(is_low_s(&with_s(
+            "7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a0"
+        )));
+        assert!(!is_low_s(&with_s(
+            "7fffffffffffffffffffffffffffffff5d576e7357a4501ddfe92f46681b20a1"
+        )));
+
+        assert!(!is_low_s(&[]));
+        assert!(!is_low_s(&low_s[..20]));
+    }
+
+    #[test]
+    fn test_nested_p2wpkh_script_code() {
+        // BIP-143 P2SH-P2WPKH example
+        let pubkey_hash: [u8; 20] = hex::decode("79091972186c449eb1ded22b78e40d009bdf0089")
+            .unwrap()
+            .try_into()
+            .unwrap();
+        let script_code = Script::nested_p2wpkh_script_code(pubkey_hash);
+
+        assert_eq!(
+            encode(&script_code.bytes),
+            "76a91479091972186c449eb1ded22b78e40d009bdf008988ac"
+        );
+        // Serialized with its length prefix as it appears in the sighash preimage
+        assert_eq!(
+            encode(script_code.to_bytes()),
+            "1976a91479091972186c449eb1ded22b78e40d009bdf008988ac"
+        );
+    }
+
+    #[test]
+    fn test_decode_and_display() {
+        let hex_tx = "02000000\
+                      01\
+                      000000000000000000000000000000000000000000000000000000000000000a\
+                      03000000\
+                      02abcd\
+                      feffffff\
+                      e8030000";
+        let expected = "Version: 2\n\n\
+                        Input Count: 1\n\n\
+                        Input 0:\n\n\
+                        \x20 Previous Output Txid: \
+                        000000000000000000000000000000000000000000000000000000000000000a\n\n\
+                        \x20 Previous Output Vout: 3\n\n\
+                        \x20 ScriptSig Length: 2\n\n\
+                        \x20 ScriptSig Bytes: abcd\n\n\
+                        \x20 Sequence: 4294967294\n\n\
+                        Lock Time: 1000\n";
+        assert_eq!(decode_and_display(hex_tx).unwrap(), expected);
+
+        assert_eq!(decode_and_display("zz"), Err(BitcoinError::InvalidFormat));
+        assert_eq!(
+            decode_and_display(&format!("{}00", hex_tx)),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            decode_and_display(&hex_tx[..hex_tx.len() - 2]),
+            Err(BitcoinError::InsufficientBytes)
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_tx_write_to() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            7,
+        );
+
+        let mut buf = vec![0xDE, 0xAD];
+        tx.write_to(&mut buf);
+        assert_eq!(buf[..2], [0xDE, 0xAD]);
+        assert_eq!(buf[2..], tx.to_bytes()[..]);
+
+        // Reusing a cleared buffer yields exactly one serialization
+        buf.clear();
+        tx.write_to(&mut buf);
+        assert_eq!(buf, tx.to_bytes());
+
+        // Writing into a buffer with room to spare never reallocates it
+        let start = buf.as_ptr();
+        buf.clear();
+        tx.write_to(&mut buf);
+        assert_eq!(buf.as_ptr(), start);
+
+        // The component writers append the same bytes their to_bytes would
+        let input = &tx.inputs[0];
+        let mut parts = Vec::new();
+        input.previous_output.write_to(&mut parts);
+        input.script_sig.write_to(&mut parts);
+        assert_eq!(
+            parts,
+            [
+                input.previous_output.to_bytes(),
+                input.script_sig.to_bytes()
+            ]
+            .concat()
+        );
+        let mut input_buf = Vec::new();
+        input.write_to(&mut input_buf);
+        assert_eq!(input_buf, input.to_bytes());
+    }
+
+    #[test]
+    fn test_bitcoin_tx_estimate_signed_vsize() {
+        let unsigned = |count: usize| {
+            BitcoinTransaction::new(
+                2,
+                (0..count)
+                    .map(|i| {
+                        TransactionInput::new(
+                            OutPoint::new(dummy_txid(i as u8), 0),
+                            Script::new(vec![]),
+                            0xFFFFFFFF,
+                        )
+                    })
+                    .collect(),
+                0,
+            )
+        };
+
+        // base 4 + 1 + 41 + 4 = 50 bytes, witness 2 + 108 = 110 bytes
+        // weight 50 * 4 + 110 = 310 => vsize 78
+        assert_eq!(unsigned(1).estimate_signed_vsize(&[InputType::P2wpkh]), 78);
+
+        // Legacy input: scriptSig grows to 107 bytes (+1 byte prefix), no witness
+        assert_eq!(
+            unsigned(1).estimate_signed_vsize(&[InputType::P2pkh]),
+            50 + 107
+        );
+
+        // Mixed: the P2PKH input gets an empty witness item count once segwit is used
+        // base 4 + 1 + (41 + 107) + 41 + 4 = 198, witness 2 + 1 + 108 = 111
+        assert_eq!(
+            unsigned(2).estimate_signed_vsize(&[InputType::P2pkh, InputType::P2wpkh]),
+            (198 * 4 + 111usize).div_ceil(4)
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_tx_verify_roundtrip() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![
+                TransactionInput::new(
+                    OutPoint::new(dummy_txid(1), 0),
+                    Script::new(vec![0x01, 0x02]),
+                    0xFFFFFFFF,
+                ),
+                TransactionInput::new(
+                    OutPoint::new(dummy_txid(2), 1),
+                    Script::new(vec![0x51; 300]),
+                    0,
+                ),
+            ],
+            600000,
+        );
+        let bytes = tx.to_bytes();
+        assert!(BitcoinTransaction::verify_roundtrip(&bytes));
+        assert!(BitcoinTransaction::verify_roundtrip(
+            &BitcoinTransaction::new(1, vec![], 0).to_bytes()
+        ));
+
+        // Input count 2 re-encoded as a non-canonical 0xFD 0x02 0x00 parses, but
+        // re-serializes canonically
+        let mut non_canonical = bytes[..4].to_vec();
+        non_canonical.extend([0xFD, 0x02, 0x00]);
+        non_canonical.extend(&bytes[5..]);
+        assert!(BitcoinTransaction::from_bytes(&non_canonical).is_ok());
+        assert!(!BitcoinTransaction::verify_roundtrip(&non_canonical));
+
+        // Trailing bytes and truncation
+        let mut trailing = bytes.clone();
+        trailing.push(0x00);
+        assert!(!BitcoinTransaction::verify_roundtrip(&trailing));
+        assert!(!BitcoinTransaction::verify_roundtrip(
+            &bytes[..bytes.len() - 1]
+        ));
+        assert!(!BitcoinTransaction::verify_roundtrip(&[]));
+    }
+
+    #[test]
+    fn test_outpoint_display() {
+        let display_txid = "0437cd7f8525ceed2324359c2d0ba26006d92d856a9c20fa0241106ee5a597c9";
+        let outpoint = OutPoint::from_display_txid(display_txid, 0).unwrap();
+        assert_eq!(outpoint.to_string(), format!("{}:0", display_txid));
+
+        let coinbase = OutPoint::new([0u8; 32], 0xFFFFFFFF);
+        assert!(coinbase.is_null());
+        assert_eq!(coinbase.to_string(), "coinbase");
+
+        let max_vout = OutPoint::from_display_txid(display_txid, 0xFFFFFFFF).unwrap();
+        assert!(!max_vout.is_null());
+        assert_eq!(max_vout.to_string(), format!("{}:0xffffffff", display_txid));
+
+        assert!(!OutPoint::new([0u8; 32], 0).is_null());
+    }
+
+    #[test]
+    fn test_tx_input_sequence_bits() {
+        let bits = |sequence| {
+            TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![]),
+                sequence,
+            )
+            .sequence_bits()
+        };
+
+        // Final sequence: relative locktime disabled
+        assert_eq!(
+            bits(0xFFFFFFFF),
+            SequenceBits {
+                disable_flag: true,
+                type_flag: true,
+                value_field: 0xFFFF,
+            }
+        );
+
+        // 144 blocks (about a day)
+        assert_eq!(
+            bits(144),
+            SequenceBits {
+                disable_flag: false,
+                type_flag: false,
+                value_field: 144,
+            }
+        );
+
+        // Time-based: 16 * 512 seconds
+        assert_eq!(
+            bits(0x00400010),
+            SequenceBits {
+                disable_flag: false,
+                type_flag: true,
+                value_field: 16,
+            }
+        );
+    }
+
+    #[test]
+    fn test_pluggable_hash_backend() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Backend that delegates to the built-in one while counting invocations
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+        struct CountingSha256;
+        impl Hasher256 for CountingSha256 {
+            fn hash(data: &[u8]) -> [u8; 32] {
+                CALLS.fetch_add(1, Ordering::SeqCst);
+                BuiltinSha256::hash(data)
+            }
+        }
+
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x01, 0x02]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+
+        assert_eq!(tx.compute_txid_with::<CountingSha256>(), tx.compute_txid());
+        assert_eq!(tx.compute_txid_with::<BuiltinSha256>(), tx.compute_txid());
+        assert_eq!(CALLS.load(Ordering::SeqCst), 2);
+
+        assert_eq!(sha256d_with::<CountingSha256>(b"abc"), sha256d(b"abc"));
+    }
+
+    #[test]
+    fn test_bitcoin_tx_unsigned_input_indices() {
+        let input = |script: Vec<u8>| {
+            TransactionInput::new(OutPoint::new(dummy_txid(1), 0), Script::new(script), 0)
+        };
+        let half_signed = BitcoinTransaction::new(
+            2,
+            vec![
+                input(vec![0x01, 0xAA]),
+                input(vec![]),
+                input(vec![0x01, 0xBB]),
+                input(vec![]),
+            ],
+            0,
+        );
+        assert_eq!(half_signed.unsigned_input_indices(), vec![1, 3]);
+        assert!(!half_signed.is_signed());
+
+        let empty = BitcoinTransaction::new(2, vec![], 0);
+        assert!(empty.unsigned_input_indices().is_empty());
+    }
+
+    #[test]
+    fn test_compact_size_display() {
+        assert_eq!(CompactSize::new(100).to_string(), "100 (1-byte)");
+        assert_eq!(CompactSize::new(300).to_string(), "300 (3-byte)");
+        assert_eq!(CompactSize::new(70000).to_string(), "70000 (5-byte)");
+        assert_eq!(
+            CompactSize::new(u64::MAX).to_string(),
+            "18446744073709551615 (9-byte)"
+        );
+    }
+
+    #[test]
+    fn test_bitcoin_tx_from_sighash_preimage() {
+        // Preimage for signing input 0: its scriptSig replaced by the prevout scriptPubKey
+        let mut script_pubkey = vec![0x76, 0xA9, 0x14];
+        script_pubkey.extend([0x22; 20]);
+        script_pubkey.extend([0x88, 0xAC]);
+        let tx = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(5), 1),
+                Script::new(script_pubkey),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+
+        let mut preimage = tx.to_bytes();
+        preimage.extend(1u32.to_le_bytes());
+
+        let (parsed, sighash_type) = BitcoinTransaction::from_sighash_preimage(&preimage).unwrap();
+        assert_eq!(parsed, tx);
+        assert_eq!(sighash_type, 1);
+
+        assert_eq!(
+            BitcoinTransaction::from_sighash_preimage(&tx.to_bytes()),
+            Err(BitcoinError::InsufficientBytes)
+        );
+        preimage.push(0x00);
+        assert_eq!(
+            BitcoinTransaction::from_sighash_preimage(&preimage),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_script_pushed_data() {
+        // OP_RETURN <3 bytes> OP_1 OP_PUSHDATA1 <2 bytes>
+        let script = Script::new(vec![
+            0x6A, 0x03, 0x6F, 0x72, 0x64, 0x51, 0x4C, 0x02, 0xBE, 0xEF,
+        ]);
+        assert_eq!(
+            script.pushed_data(),
+            vec![&[0x6F, 0x72, 0x64][..], &[0xBE, 0xEF][..]]
+        );
+
+        assert_eq!(Script::new(vec![0x00]).pushed_data(), vec![&[] as &[u8]]);
+        assert!(Script::new(vec![0x76, 0xAC]).pushed_data().is_empty());
+        assert!(Script::new(vec![0x03, 0x01]).pushed_data().is_empty());
+    }
+
+    #[test]
+    fn test_script_from_bytes_validated() {
+        let valid = Script::new(vec![0x4C, 0x02, 0xAA, 0xBB, 0xAC]);
+        let bytes = valid.to_bytes();
+        assert_eq!(
+            Script::from_bytes_validated(&bytes),
+            Ok((valid, bytes.len()))
+        );
+
+        // OP_PUSHDATA1 claiming 5 bytes with only one left in the script
+        let dangling = Script::new(vec![0x76, 0x4C, 0x05, 0x01]).to_bytes();
+        assert!(Script::from_bytes(&dangling).is_ok());
+        assert_eq!(
+            Script::from_bytes_validated(&dangling),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        // OP_PUSHDATA1 missing its length byte entirely
+        assert_eq!(
+            Script::from_bytes_validated(&[0x01, 0x4C]),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            Script::from_bytes_validated(&[0x02, 0x4C]),
+            Err(BitcoinError::InsufficientBytes)
+        );
+    }
+
+    #[test]
+    fn test_txid_serde_binary_and_json() {
+        use crate::common::{BinaryDeserializer, BinarySerializer};
+        use serde::de::IntoDeserializer;
+        use serde::de::value::Error as ValueError;
+        use serde::{Deserialize, Serialize};
+
+        let txid = Txid(dummy_txid(0x5A));
+
+        // Binary: exactly the 32 raw bytes, half the size of the hex string
+        let binary = txid.serialize(BinarySerializer).unwrap();
+        assert_eq!(binary, txid.0.to_vec());
+        assert_eq!(
+            Txid::deserialize(BinaryDeserializer(&binary)).unwrap(),
+            txid
+        );
+        assert!(Txid::deserialize(BinaryDeserializer(&binary[..31])).is_err());
+
+        // JSON stays hex
+        let json = serde_json::to_string(&txid).unwrap();
+        assert_eq!(json, format!("\"{}\"", encode(txid.0)));
+        assert_eq!(serde_json::from_str::<Txid>(&json).unwrap(), txid);
+
+        // Value deserializers from serde itself are human-readable and take the hex path
+        let hex_str = encode(txid.0);
+        let from_str: Result<Txid, ValueError> =
+            Txid::deserialize(hex_str.as_str().into_deserializer());
+        assert_eq!(from_str.unwrap(), txid);
+    }
+
+    #[test]
+    fn test_bitcoin_tx_size_delta_add_input() {
+        let input = TransactionInput::new(OutPoint::new(dummy_txid(1), 0), Script::new(vec![]), 0);
+        let with_inputs = |count| BitcoinTransaction::new(2, vec![input.clone(); count], 0);
+
+        // 251 -> 252 inputs keeps a 1-byte count
+        let tx = with_inputs(251);
+        assert_eq!(tx.size_delta_add_input(0, 0), 41);
+
+        // 252 -> 253 inputs grows the count prefix from 1 to 3 bytes
+        let tx = with_inputs(252);
+        assert_eq!(tx.size_delta_add_input(0, 0), 43);
+        let mut grown = tx.clone();
+        grown.inputs.push(input.clone());
+        assert_eq!(grown.to_bytes().len() - tx.to_bytes().len(), 43);
+
+        // A 107-byte scriptSig: 36 + 1 + 107 + 4
+        assert_eq!(with_inputs(1).size_delta_add_input(107, 0), 148);
+        assert_eq!(with_inputs(1).weight_delta_add_input(107, 0), 148 * 4);
+
+        // First witness input (108-byte P2WPKH witness) on a 1-input legacy transaction:
+        // base 41, witness 108 + marker/flag + 1 empty witness for the existing input
+        let tx = with_inputs(1);
+        assert_eq!(tx.size_delta_add_input(0, 108), 41 + 111);
+        assert_eq!(tx.weight_delta_add_input(0, 108), 41 * 4 + 111);
+    }
+
+    #[test]
+    fn test_is_psbt() {
+        let mut psbt = b"psbt".to_vec();
+        psbt.extend([0xFF, 0x01, 0x00]);
+        assert!(is_psbt(&psbt));
+        assert!(!is_psbt(&psbt[..4]));
+
+        let raw_tx = BitcoinTransaction::new(2, vec![], 0).to_bytes();
+        assert!(!is_psbt(&raw_tx));
+        assert!(!is_psbt(b"psbt\x00"));
+    }
+
+    #[test]
+    fn test_from_bytes_short_lengths() {
+        let compact = CompactSize::new(0x1_0000_0000).to_bytes();
+        let outpoint = OutPoint::new(dummy_txid(1), 2).to_bytes();
+        let script = Script::new(vec![0x51; 3]).to_bytes();
+        let input = TransactionInput::new(
+            OutPoint::new(dummy_txid(1), 2),
+            Script::new(vec![0x51; 3]),
+            0xFFFFFFFF,
+        );
+        let input_bytes = input.to_bytes();
+        let tx = BitcoinTransaction::new(2, vec![input], 0).to_bytes();
+
+        for len in 0..compact.len() {
+            assert_eq!(
+                CompactSize::from_bytes(&compact[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+        for len in 0..outpoint.len() {
+            assert_eq!(
+                OutPoint::from_bytes(&outpoint[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+        for len in 0..script.len() {
+            assert_eq!(
+                Script::from_bytes(&script[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+        for len in 0..input_bytes.len() {
+            assert_eq!(
+                TransactionInput::from_bytes(&input_bytes[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+        for len in 0..tx.len() {
+            assert_eq!(
+                BitcoinTransaction::from_bytes(&tx[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+        assert!(BitcoinTransaction::from_bytes(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_coinbase_height() {
+        // Coinbase of block 400000: height push 03 801a06 followed by extra nonce data
+        let script_sig = Script::new(hex::decode("03801a060400000000").unwrap());
+        let coinbase_input =
+            TransactionInput::new(OutPoint::new([0u8; 32], u32::MAX), script_sig, 0xFFFFFFFF);
+        let coinbase = BitcoinTransaction::new(1, vec![coinbase_input], 0);
+        assert!(coinbase.is_coinbase());
+        assert_eq!(coinbase.coinbase_height(), Some(400000));
+
+        // Real coinbases continue with arbitrary bytes: "/BTC/" reads as a 47-byte push
+        // that runs past the end, which must not hide the height
+        let tagged = TransactionInput::new(
+            OutPoint::new([0u8; 32], u32::MAX),
+            Script::new(hex::decode("03801a062f4254432f").unwrap()),
+            0xFFFFFFFF,
+        );
+        assert_eq!(
+            BitcoinTransaction::new(1, vec![tagged], 0).coinbase_height(),
+            Some(400000)
+        );
+
+        // Pre-BIP-34 coinbase (genesis-style) starts with a non-height push
+        let old_input = TransactionInput::new(
+            OutPoint::new([0u8; 32], u32::MAX),
+            Script::new(hex::decode("04ffff001d0104").unwrap()),
+            0xFFFFFFFF,
+        );
+        let old = BitcoinTransaction::new(1, vec![old_input], 0);
+        assert!(old.is_coinbase());
+        assert_eq!(old.coinbase_height(), None);
+
+        let spend = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x03, 0x80, 0x1a, 0x06]),
+                0,
+            )],
+            0,
+        );
+        assert!(!spend.is_coinbase());
+        assert_eq!(spend.coinbase_height(), None);
+
+        let no_push = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new([0u8; 32], u32::MAX),
+                Script::new(vec![0x6a]),
+                0,
+            )],
+            0,
+        );
+        assert_eq!(no_push.coinbase_height(), None);
+    }
+
+    #[test]
+    fn test_bip143_hash_prevouts_and_sequence() {
+        // Native P2WPKH example from BIP-143
+        let txid = |h: &str| -> [u8; 32] { hex::decode(h).unwrap().try_into().unwrap() };
+        let tx = BitcoinTransaction::new(
+            1,
+            vec![
+                TransactionInput::new(
+                    OutPoint::new(
+                        txid("fff7f7881a8099afa6940d42d1e7f6362bec38171ea3edf433541db4e4ad969f"),
+                        0,
+                    ),
+                    Script::new(vec![]),
+                    0xFFFFFFEE,
+                ),
+                TransactionInput::new(
+                    OutPoint::new(
+                        txid("ef51e1b804cc89d182d279655c3aa89e815b1b309fe287d9b2b55d57b90ec68a"),
+                        1,
+                    ),
+                    Script::new(vec![]),
+                    0xFFFFFFFF,
+                ),
+            ],
+            0x11,
+        );
+
+        assert_eq!(
+            encode(tx.hash_prevouts()),
+            "96b827c8483d4e9b96712b6713a7b68d6e8003a781feba36c31143470b4efd37"
+        );
+        assert_eq!(
+            encode(tx.hash_sequence()),
+            "52b0a642eea2fb7ae638c36f6252b6750293dbe574a806984b8e4d8548339a3b"
+        );
+    }
+
+    #[test]
+    fn test_script_to_address() {
+        // Genesis coinbase key hash
+        let p2pkh =
+            Script::new(hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap());
+        assert_eq!(
+            p2pkh.to_address(Network::Mainnet).as_deref(),
+            Some("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa")
+        );
+
+        // BIP-173 example program
+        let p2wpkh =
+            Script::new(hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+        assert_eq!(
+            p2wpkh.to_address(Network::Testnet).as_deref(),
+            Some("tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx")
+        );
+        assert_eq!(
+            p2wpkh.to_address(Network::Mainnet).as_deref(),
+            Some("bc1qw508d6qejxtdg4y5r3zarvary0c5xw7kv8f3t4")
+        );
+
+        // BIP-350 taproot example
+        let p2tr = Script::new(
+            hex::decode("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap(),
+        );
+        assert_eq!(
+            p2tr.to_address(Network::Mainnet).as_deref(),
+            Some("bc1p0xlxvlhemja6c4dqv22uapctqupfhlxm9h8z3k2e72q4k9hcz7vqzk5jj0")
+        );
+
+        assert_eq!(
+            Script::new(vec![0x6a, 0x01, 0x00]).to_address(Network::Mainnet),
+            None
+        );
+    }
+
+    #[test]
+    fn test_network_constants() {
+        assert_eq!(Network::Mainnet.p2pkh_version(), 0x00);
+        assert_eq!(Network::Mainnet.p2sh_version(), 0x05);
+        assert_eq!(Network::Mainnet.bech32_hrp(), "bc");
+        assert_eq!(Network::Mainnet.magic(), [0xf9, 0xbe, 0xb4, 0xd9]);
+
+        assert_eq!(Network::Testnet.p2pkh_version(), 0x6f);
+        assert_eq!(Network::Testnet.p2sh_version(), 0xc4);
+        assert_eq!(Network::Testnet.bech32_hrp(), "tb");
+        assert_eq!(Network::Testnet.magic(), [0x0b, 0x11, 0x09, 0x07]);
+
+        assert_eq!(Network::Regtest.p2pkh_version(), 0x6f);
+        assert_eq!(Network::Regtest.p2sh_version(), 0xc4);
+        assert_eq!(Network::Regtest.bech32_hrp(), "bcrt");
+        assert_eq!(Network::Regtest.magic(), [0xfa, 0xbf, 0xb5, 0xda]);
+
+        assert_eq!(Network::Signet.p2pkh_version(), 0x6f);
+        assert_eq!(Network::Signet.p2sh_version(), 0xc4);
+        assert_eq!(Network::Signet.bech32_hrp(), "tb");
+        assert_eq!(Network::Signet.magic(), [0x0a, 0x03, 0xcf, 0x40]);
+
+        // Magic plugs straight into P2P framing
+        let tx = BitcoinTransaction::new(1, vec![], 0);
+        assert_eq!(
+            tx.to_p2p_tx_message(Network::Mainnet.magic())[..4],
+            [0xf9, 0xbe, 0xb4, 0xd9]
+        );
+    }
+
+    #[test]
+    fn test_address_to_script() {
+        let p2wpkh =
+            Script::new(hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+        let address = p2wpkh.to_address(Network::Testnet).unwrap();
+        assert_eq!(
+            address_to_script(&address, Network::Testnet),
+            Ok(p2wpkh.clone())
+        );
+        // Uppercase is valid bech32, mixed case is not
+        assert_eq!(
+            address_to_script(&address.to_uppercase(), Network::Testnet),
+            Ok(p2wpkh)
+        );
+        assert_eq!(
+            address_to_script(
+                "tb1QW508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsx",
+                Network::Testnet
+            ),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        let p2pkh =
+            Script::new(hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap());
+        assert_eq!(
+            address_to_script("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", Network::Mainnet),
+            Ok(p2pkh)
+        );
+
+        let p2tr = Script::new(
+            hex::decode("512079be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+                .unwrap(),
+        );
+        let taproot = p2tr.to_address(Network::Mainnet).unwrap();
+        assert_eq!(address_to_script(&taproot, Network::Mainnet), Ok(p2tr));
+
+        // Wrong network, broken checksums
+        assert_eq!(
+            address_to_script(&address, Network::Mainnet),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            address_to_script("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNa", Network::Testnet),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            address_to_script("1A1zP1eP5QGefi2DMPTfTL5SLmv7DivfNb", Network::Mainnet),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            address_to_script(
+                "tb1qw508d6qejxtdg4y5r3zarvary0c5xw7kxpjzsy",
+                Network::Testnet
+            ),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_txid_from_str_and_prefix() {
+        let display = "4a5e1e4baab89f3a32518a88c31bc87f618f76673e2cc77ab2127b7afdeda33b";
+        let txid: Txid = display.parse().unwrap();
+        assert_eq!(
+            OutPoint::new(txid.0, 0).to_string(),
+            format!("{}:0", display)
+        );
+        assert_eq!(format!("0x{}", display).parse::<Txid>(), Ok(txid.clone()));
+
+        // Strict: exactly 64 hex chars
+        assert_eq!(
+            "4a5e1e4baa".parse::<Txid>(),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            format!("{}00", display).parse::<Txid>(),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            display.replace('a', "g").parse::<Txid>(),
+            Err(BitcoinError::InvalidFormat)
+        );
+
+        let prefix = Txid::from_str_prefix("4a5e1e4baa").unwrap();
+        assert!(prefix.matches(&txid));
+        let other: Txid = "4a5e1e4bab000000000000000000000000000000000000000000000000000000"
+            .parse()
+            .unwrap();
+        assert!(!prefix.matches(&other));
+
+        // Odd lengths match on the high nibble
+        let odd = Txid::from_str_prefix("4a5e1e4ba").unwrap();
+        assert!(odd.matches(&txid));
+        assert!(odd.matches(&other));
+        assert!(!Txid::from_str_prefix("4a5e1e4bb").unwrap().matches(&txid));
+
+        assert!(Txid::from_str_prefix(display).unwrap().matches(&txid));
+        assert_eq!(Txid::from_str_prefix(""), Err(BitcoinError::InvalidFormat));
+        assert_eq!(
+            Txid::from_str_prefix(&format!("{}0", display)),
+            Err(BitcoinError::InvalidFormat)
+        );
+        assert_eq!(
+            Txid::from_str_prefix("xyz"),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_compact_size_write_to() {
+        let mut buf = vec![0xAA];
+        CompactSize::new(0xFC).write_to(&mut buf);
+        CompactSize::new(0x1_0000).write_to(&mut buf);
+        assert_eq!(buf, vec![0xAA, 0xFC, 0xFE, 0x00, 0x00, 0x01, 0x00]);
+
+        for value in [0, 0xFD, 0xFFFF, 0x1_0000_0000, u64::MAX] {
+            let size = CompactSize::new(value);
+            let mut buf = Vec::new();
+            size.write_to(&mut buf);
+            assert_eq!(buf, size.to_bytes());
+            assert_eq!(buf.len(), size.encoded_len());
+        }
+    }
+
+    #[test]
+    fn test_first_byte_diff() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x51]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        let bytes = tx.to_bytes();
+        assert_eq!(BitcoinTransaction::roundtrip_diff(&bytes), None);
+
+        // Non-canonical input count 0xFD 0x01 0x00: re-serializing writes 0x01 at offset 4
+        let mut non_canonical = bytes[..4].to_vec();
+        non_canonical.extend([0xFD, 0x01, 0x00]);
+        non_canonical.extend(&bytes[5..]);
+        assert_eq!(
+            BitcoinTransaction::roundtrip_diff(&non_canonical),
+            Some((4, 0xFD, 0x01))
+        );
+
+        // Trailing byte past the parsed transaction
+        let mut trailing = bytes.clone();
+        trailing.push(0xAB);
+        assert_eq!(
+            BitcoinTransaction::roundtrip_diff(&trailing),
+            Some((bytes.len(), 0xAB, 0x00))
+        );
+
+        // Flip the sequence's first byte
+        let mut corrupted = bytes.clone();
+        corrupted[43] = 0x00;
+        assert_eq!(first_byte_diff(&bytes, &corrupted), Some((43, 0xFF, 0x00)));
+
+        assert_eq!(first_byte_diff(&bytes, &bytes), None);
+        assert_eq!(first_byte_diff(&[1, 2], &[1, 2, 3]), Some((2, 0x00, 0x03)));
+        assert_eq!(first_byte_diff(&[], &[]), None);
+    }
+
+    #[test]
+    fn test_compact_size_as_usize() {
+        assert_eq!(CompactSize::new(0).as_usize(), Ok(0));
+        assert_eq!(CompactSize::new(0xFFFF).as_usize(), Ok(0xFFFF));
+
+        let big = CompactSize::new(u32::MAX as u64 + 1);
+        if usize::BITS < 64 {
+            assert_eq!(big.as_usize(), Err(BitcoinError::InvalidFormat));
+            assert_eq!(
+                Script::from_bytes(&big.to_bytes()),
+                Err(BitcoinError::InvalidFormat)
+            );
+        } else {
+            assert_eq!(big.as_usize(), Ok(u32::MAX as usize + 1));
+            // The length fits but the bytes aren't there
+            assert_eq!(
+                Script::from_bytes(&big.to_bytes()),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+    }
+
+    #[test]
+    fn test_empty_transaction_serialization() {
+        // No output vector in this model, so 9 bytes rather than the 10 of a full
+        // transaction (02000000 00 00 00000000)
+        let tx = BitcoinTransaction::new(2, vec![], 0);
+        let bytes = tx.to_bytes();
+        assert_eq!(encode(&bytes), "020000000000000000");
+        assert_eq!(bytes.len(), 9);
+
+        assert_eq!(BitcoinTransaction::from_bytes(&bytes), Ok((tx.clone(), 9)));
+        assert!(BitcoinTransaction::verify_roundtrip(&bytes));
+
+        // The zero input count sits where a segwit marker would, but lock_time 0 follows
+        assert!(!has_witness_marker(&bytes));
+    }
+
+    #[test]
+    fn test_script_sig_sizes() {
+        let bloated = TransactionInput::new(
+            OutPoint::new(dummy_txid(1), 0),
+            Script::new(vec![0x01; 100]),
+            0xFFFFFFFF,
+        );
+        let empty = TransactionInput::new(OutPoint::new(dummy_txid(2), 1), Script::new(vec![]), 0);
+        let wide = TransactionInput::new(
+            OutPoint::new(dummy_txid(3), 2),
+            Script::new(vec![0x02; 300]),
+            0,
+        );
+        assert_eq!(bloated.script_sig_size(), 101);
+        assert_eq!(empty.script_sig_size(), 1);
+        assert_eq!(wide.script_sig_size(), 303);
+        assert_eq!(
+            bloated.script_sig_size(),
+            bloated.script_sig.to_bytes().len()
+        );
+
+        let tx = BitcoinTransaction::new(1, vec![bloated, empty, wide], 0);
+        assert_eq!(tx.total_script_sig_size(), 405);
+        assert_eq!(
+            BitcoinTransaction::new(1, vec![], 0).total_script_sig_size(),
+            0
+        );
+    }
+
+    #[test]
+    fn test_script_witness_program() {
+        let p2wpkh =
+            Script::new(hex::decode("0014751e76e8199196d454941c45d1b3a323f1433bd6").unwrap());
+        assert_eq!(
+            p2wpkh.witness_program(),
+            Some((
+                0,
+                hex::decode("751e76e8199196d454941c45d1b3a323f1433bd6").unwrap()
+            ))
+        );
+
+        let mut taproot = vec![0x51, 0x20];
+        taproot.extend([0x79; 32]);
+        assert_eq!(
+            Script::new(taproot).witness_program(),
+            Some((1, vec![0x79; 32]))
+        );
+
+        // OP_16 with the shortest and longest programs
+        assert_eq!(
+            Script::new(vec![0x60, 0x02, 0xAA, 0xBB]).witness_program(),
+            Some((16, vec![0xAA, 0xBB]))
+        );
+        let mut longest = vec![0x52, 40];
+        longest.extend([0x01; 40]);
+        assert_eq!(
+            Script::new(longest).witness_program().map(|(v, _)| v),
+            Some(2)
+        );
+
+        // Too short, too long, wrong push length, non-version opcode
+        assert_eq!(Script::new(vec![0x00, 0x01, 0xAA]).witness_program(), None);
+        let mut too_long = vec![0x51, 41];
+        too_long.extend([0x01; 41]);
+        assert_eq!(Script::new(too_long).witness_program(), None);
+        assert_eq!(
+            Script::new(vec![0x00, 0x03, 0xAA, 0xBB]).witness_program(),
+            None
+        );
+        assert_eq!(
+            Script::new(vec![0x4f, 0x02, 0xAA, 0xBB]).witness_program(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_tagged_hash() {
+        // TapLeaf of leaf version 0xc0 with the script OP_TRUE
+        assert_eq!(
+            encode(tagged_hash("TapLeaf", &[0xc0, 0x01, 0x51])),
+            "a85b2107f791b26a84e7586c28cec7cb61202ed3d01944d832500f363782d675"
+        );
+        assert_eq!(
+            encode(tagged_hash("TapSighash", &[])),
+            "dabc11914abcd8072900042a2681e52f8dba99ce82e224f97b5fdb7cd4b9c803"
+        );
+        // TapTweak of the generator's x-coordinate with no script tree
+        let g_x = hex::decode("79be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798")
+            .unwrap();
+        assert_eq!(
+            encode(tagged_hash("TapTweak", &g_x)),
+            "3cf5216d476a5e637bf0da674e50ddf55c403270dd36494dfcca438132fa30e7"
+        );
+        assert_ne!(tagged_hash("TapLeaf", b"x"), tagged_hash("TapBranch", b"x"));
+    }
+
+    #[test]
+    fn test_script_new_p2tr() {
+        let key = [0x79; 32];
+        let script = Script::new_p2tr(key);
+
+        let mut expected = vec![0x51, 0x20];
+        expected.extend(key);
+        assert_eq!(script.bytes, expected);
+        assert!(script.is_p2tr());
+        assert_eq!(script.witness_program(), Some((1, key.to_vec())));
+
+        // Other witness versions and lengths are not taproot
+        let mut v0 = vec![0x00, 0x20];
+        v0.extend(key);
+        assert!(!Script::new(v0).is_p2tr());
+        assert!(!Script::new(vec![0x51, 0x02, 0xAA, 0xBB]).is_p2tr());
+        assert!(!Script::new(vec![]).is_p2tr());
+    }
+
+    #[test]
+    fn test_borrowed_transaction_matches_owned() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![
+                TransactionInput::new(
+                    OutPoint::new(dummy_txid(1), 0),
+                    Script::new(vec![0x51; 300]),
+                    0xFFFFFFFE,
+                ),
+                TransactionInput::new(OutPoint::new(dummy_txid(2), 7), Script::new(vec![]), 5),
+            ],
+            700_000,
+        );
+        let bytes = tx.to_bytes();
+
+        let (borrowed, consumed) = BorrowedTransaction::from_bytes_borrowed(&bytes).unwrap();
+        assert_eq!(consumed, bytes.len());
+        assert_eq!(borrowed.version, tx.version);
+        assert_eq!(borrowed.lock_time, tx.lock_time);
+        for (view, input) in borrowed.inputs.iter().zip(&tx.inputs) {
+            assert_eq!(view.previous_output, input.previous_output);
+            assert_eq!(view.script_sig, input.script_sig.as_bytes());
+            assert_eq!(view.sequence, input.sequence);
+        }
+        assert_eq!(borrowed.to_transaction(), tx);
+
+        // The scriptSig points into the original buffer rather than a copy
+        let range = bytes.as_ptr_range();
+        assert!(range.contains(&borrowed.inputs[0].script_sig.as_ptr()));
+
+        for len in 0..bytes.len() {
+            assert_eq!(
+                BorrowedTransaction::from_bytes_borrowed(&bytes[..len]),
+                Err(BitcoinError::InsufficientBytes)
+            );
+        }
+    }
+
+    #[test]
+    fn test_without_input() {
+        let inputs: Vec<TransactionInput> = (1..=3)
+            .map(|i| {
+                TransactionInput::new(
+                    OutPoint::new(dummy_txid(i), i as u32),
+                    Script::new(vec![i]),
+                    0,
+                )
+            })
+            .collect();
+        let tx = BitcoinTransaction::new(2, inputs.clone(), 0);
+
+        let trimmed = tx.without_input(1).unwrap();
+        assert_eq!(trimmed.inputs, vec![inputs[0].clone(), inputs[2].clone()]);
+        assert_eq!(trimmed.version, 2);
+        assert_eq!(tx.inputs.len(), 3);
+
+        assert_eq!(tx.without_input(3), Err(BitcoinError::InvalidFormat));
+        assert_eq!(
+            BitcoinTransaction::new(2, vec![], 0).without_input(0),
+            Err(BitcoinError::InvalidFormat)
+        );
+    }
+
+    #[test]
+    fn test_sigop_count() {
+        let p2pkh =
+            Script::new(hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap());
+        assert_eq!(p2pkh.sigop_count(false), 1);
+        assert_eq!(p2pkh.sigop_count(true), 1);
+
+        // 2-of-3 bare multisig: OP_2 <key> <key> <key> OP_3 OP_CHECKMULTISIG
+        let mut multisig = vec![0x52];
+        for _ in 0..3 {
+            multisig.push(33);
+            multisig.extend([0x02; 33]);
+        }
+        multisig.extend([0x53, 0xae]);
+        let multisig = Script::new(multisig);
+        assert_eq!(multisig.sigop_count(false), 20);
+        assert_eq!(multisig.sigop_count(true), 3);
+
+        // A push whose data happens to contain 0xac is not an opcode
+        assert_eq!(Script::new(vec![0x01, 0xac]).sigop_count(false), 0);
+        // Counting stops at a truncated push
+        assert_eq!(
+            Script::new(vec![0xad, 0xac, 0x4c, 0x05]).sigop_count(false),
+            2
+        );
+
+        let tx = BitcoinTransaction::new(
+            1,
+            vec![
+                TransactionInput::new(OutPoint::new(dummy_txid(1), 0), p2pkh, 0),
+                TransactionInput::new(OutPoint::new(dummy_txid(2), 0), multisig, 0),
+            ],
+            0,
+        );
+        assert_eq!(tx.legacy_sigop_count(), 21);
+    }
+
+    #[test]
+    fn test_compact_size_from_iter() {
+        for value in [
+            0xFC,
+            0xFD,
+            0xFFFF,
+            0x1_0000,
+            0xFFFF_FFFF,
+            0x1_0000_0000,
+            u64::MAX,
+        ] {
+            let size = CompactSize::new(value);
+            let mut bytes = size.to_bytes();
+            bytes.push(0xEE);
+
+            let mut iter = bytes.into_iter();
+            assert_eq!(CompactSize::from_iter(&mut iter), Ok(size));
+            // Only the encoding was consumed
+            assert_eq!(iter.collect::<Vec<u8>>(), vec![0xEE]);
+        }
+
+        let mut empty = Vec::new().into_iter();
+        assert_eq!(
+            CompactSize::from_iter(&mut empty),
+            Err(BitcoinError::InsufficientBytes)
+        );
+        let mut short = vec![0xFE, 0x01, 0x02].into_iter();
+        assert_eq!(
+            CompactSize::from_iter(&mut short),
+            Err(BitcoinError::InsufficientBytes)
+        );
+    }
+
+    #[test]
+    fn test_size_summary() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![
+                TransactionInput::new(
+                    OutPoint::new(dummy_txid(1), 0),
+                    Script::new(vec![0x51; 10]),
+                    0xFFFFFFFF,
+                ),
+                TransactionInput::new(OutPoint::new(dummy_txid(2), 1), Script::new(vec![]), 0),
+            ],
+            0,
+        );
+        assert_eq!(tx.base_size(), 4 + 1 + (36 + 11 + 4) + (36 + 1 + 4) + 4);
+        assert_eq!(tx.total_size(), tx.base_size());
+        assert_eq!(tx.weight(), tx.base_size() * 4);
+        assert_eq!(tx.vsize(), tx.weight() / 4);
+
+        let summary = tx.size_summary();
+        let rows: Vec<&str> = summary.lines().collect();
+        assert_eq!(
+            rows,
+            vec![
+                format!("Base size:    {} bytes", tx.base_size()),
+                format!("Total size:   {} bytes", tx.total_size()),
+                format!("Weight:       {} WU", tx.weight()),
+                format!("Virtual size: {} vbytes", tx.vsize()),
+                String::from("Inputs:       2"),
+                String::from("Outputs:      0"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sighash_type() {
+        let parsed = SighashType::from_u32(0x81).unwrap();
+        assert_eq!(parsed, SighashType::AllPlusAnyoneCanPay);
+        assert!(parsed.is_anyonecanpay());
+        assert_eq!(parsed.to_u32(), SIGHASH_ALL | SIGHASH_ANYONECANPAY);
+
+        for value in [1, 2, 3, 0x81, 0x82, 0x83] {
+            assert_eq!(SighashType::from_u32(value).unwrap().to_u32(), value);
+        }
+        assert!(
+            !SighashType::from_u32(SIGHASH_SINGLE)
+                .unwrap()
+                .is_anyonecanpay()
+        );
+
+        for value in [0, 4, 0x80, 0x84, 0x101] {
+            assert_eq!(
+                SighashType::from_u32(value),
+                Err(BitcoinError::InvalidFormat)
+            );
+        }
+    }
+
+    #[test]
+    fn test_script_has_minimal_pushes() {
+        let p2pkh =
+            Script::new(hex::decode("76a91462e907b15cbf27d5425399ebf6f0fb50ebb88f1888ac").unwrap());
+        assert!(p2pkh.has_minimal_pushes());
+        assert!(Script::new(vec![0x00, 0x51, 0x60, 0x4f]).has_minimal_pushes());
+        assert!(Script::new(vec![0x01, 0x11]).has_minimal_pushes());
+
+        // 0x05 pushed with OP_PUSHBYTES_1 instead of OP_5, and 0x81 instead of OP_1NEGATE
+        assert!(!Script::new(vec![0x01, 0x05]).has_minimal_pushes());
+        assert!(!Script::new(vec![0x01, 0x81]).has_minimal_pushes());
+        // Empty push through PUSHDATA1 instead of OP_0
+        assert!(!Script::new(vec![0x4c, 0x00]).has_minimal_pushes());
+        // 10 bytes through PUSHDATA1 instead of a direct push
+        let mut pushdata1 = vec![0x4c, 0x0a];
+        pushdata1.extend([0xAA; 10]);
+        assert!(!Script::new(pushdata1).has_minimal_pushes());
+        // 76 bytes is the first length that needs PUSHDATA1
+        let mut boundary = vec![0x4c, 76];
+        boundary.extend([0xAA; 76]);
+        assert!(Script::new(boundary).has_minimal_pushes());
+
+        assert!(!Script::new(vec![0x02, 0xAA]).has_minimal_pushes());
+    }
+
+    #[test]
+    fn test_weight_to_vsize() {
+        assert_eq!(WITNESS_SCALE_FACTOR, 4);
+        assert_eq!(weight_to_vsize(4000), 1000);
+        assert_eq!(weight_to_vsize(4001), 1001);
+        assert_eq!(weight_to_vsize(4003), 1001);
+        assert_eq!(weight_to_vsize(4004), 1001);
+        assert_eq!(weight_to_vsize(0), 0);
+
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x51]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        assert_eq!(tx.vsize(), weight_to_vsize(tx.weight()));
+        assert_eq!(tx.vsize(), tx.to_bytes().len());
+    }
+
+    #[test]
+    fn test_hex_dump_annotated() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 1),
+                Script::new(vec![0x51, 0x52]),
+                0xFFFFFFFE,
+            )],
+            0,
+        );
+        let dump = tx.hex_dump_annotated();
+        let lines: Vec<(&str, &str)> = dump
+            .lines()
+            .map(|line| line.split_once("    ").unwrap())
+            .collect();
+
+        let labels: Vec<&str> = lines.iter().map(|(_, label)| *label).collect();
+        assert_eq!(
+            labels,
+            vec![
+                "version",
+                "input count",
+                "prevout txid",
+                "prevout vout",
+                "scriptSig length",
+                "scriptSig",
+                "sequence",
+                "lock_time"
+            ]
+        );
+        assert_eq!(lines[0].0, "02000000");
+        assert_eq!(lines[1].0, "01");
+        assert_eq!(lines[2].0, encode(dummy_txid(1)));
+        assert_eq!(lines[5].0, "5152");
+        assert_eq!(lines[6].0, "feffffff");
+
+        // Concatenating the hex column gives back the serialization
+        let hex: String = lines.iter().map(|(hex, _)| *hex).collect();
+        assert_eq!(hex, encode(tx.to_bytes()));
+
+        let empty_script = BitcoinTransaction::new(
+            1,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(2), 0),
+                Script::new(vec![]),
+                0,
+            )],
+            0,
+        );
+        assert_eq!(empty_script.hex_dump_annotated().lines().count(), 7);
+    }
+
+    #[test]
+    fn test_min_relay_fee() {
+        let tx = BitcoinTransaction::new(
+            2,
+            vec![TransactionInput::new(
+                OutPoint::new(dummy_txid(1), 0),
+                Script::new(vec![0x51; 10]),
+                0xFFFFFFFF,
+            )],
+            0,
+        );
+        // 4 + 1 + (36 + 11 + 4) + 4 = 60 vbytes
+        assert_eq!(tx.vsize(), 60);
+
+        // 60 * 1000 / 1000 divides evenly; 60 * 1234 / 1000 = 74.04 rounds up
+        assert_eq!(tx.min_relay_fee(DEFAULT_MIN_RELAY_TX_FEE), 60);
+        assert_eq!(tx.min_relay_fee(1234), 75);
+        assert_eq!(tx.min_relay_fee(100), 6);
+        assert_eq!(tx.min_relay_fee(1), 1);
+        assert_eq!(tx.min_relay_fee(0), 0);
+
+        // 60 * rate overflows u64 past u64::MAX / 60; the product saturates
+        assert_eq!(
+            tx.min_relay_fee(u64::MAX / 60),
+            (u64::MAX / 60 * 60).div_ceil(1000)
+        );
+        assert_eq!(tx.min_relay_fee(u64::MAX / 60 + 1), u64::MAX.div_ceil(1000));
+        assert_eq!(tx.min_relay_fee(u64::MAX / 10), u64::MAX.div_ceil(1000));
+    }
+
+    #[test]
+    fn test_script_length_overflow_is_error() {
+        // CompactSize u64::MAX as a script length must not overflow the offset math
+        assert!(Script::from_bytes(&[0xFF; 9]).is_err());
+
+        // version + 1 input + outpoint + FF FF..FF script length = 50 bytes
+        let mut tx_bytes = vec![0x02, 0x00, 0x00, 0x00, 0x01];
+        tx_bytes.extend(dummy_txid(1));
+        tx_bytes.extend([0x00; 4]);
+        tx_bytes.extend([0xFF; 9]);
+        assert_eq!(tx_bytes.len(), 50);
+        assert!(BitcoinTransaction::from_bytes(&tx_bytes).is_err());
+        assert!(BorrowedTransaction::from_bytes_borrowed(&tx_bytes).is_err());
+    }
 }